@@ -0,0 +1,40 @@
+//! Renders a JSON event script (see `scripts/example.json`) or a type 0/1 MIDI file
+//! to a WAV file without a plugin host.
+
+use schoffhauzer_synth::offline::midi_file::MidiFileImport;
+use schoffhauzer_synth::offline::renderer::OfflineRenderer;
+use schoffhauzer_synth::offline::script::Script;
+use schoffhauzer_synth::offline::wav::write_wav_file;
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let (input, output, sample_rate) = match args.as_slice() {
+        [_, input, output] => (input, output, None),
+        [_, input, output, sample_rate] => (input, output, Some(sample_rate.parse()?)),
+        _ => {
+            eprintln!("Usage: offline_render <script.json|song.mid> <output.wav> [MIDI sample rate]");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let is_midi = Path::new(input)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mid") || ext.eq_ignore_ascii_case("midi"));
+    let script = if is_midi {
+        let mut import = MidiFileImport::default();
+        if let Some(sample_rate) = sample_rate {
+            import.sample_rate = sample_rate;
+        }
+        import.import_file(input)?
+    } else {
+        Script::from_file(input)?
+    };
+
+    let [left, right] = OfflineRenderer::render_script(&script);
+    write_wav_file(output, script.sample_rate, &[&left, &right])?;
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,117 @@
+use crate::gui::ParamChange;
+use crate::gui::envelope_view::envelope_view;
+use crate::gui::knob::Knob;
+use crate::gui::scope_view::{SPECTRUM_LENGTH, scope_view, spectrum_view};
+use crate::gui::visualizer::{Playheads, VisualizerFeed, VisualizerReader};
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::utils::ring_buffer::RingBuffer;
+use clack_extensions::params::ParamInfo;
+use clack_plugin::events::Pckn;
+use clack_plugin::events::event_types::ParamValueEvent;
+use clack_plugin::utils::Cookie;
+use egui_baseview::egui::{CentralPanel, Context, Ui, vec2};
+use std::sync::Arc;
+
+type Params = SchoffhauzerSynthPluginParams;
+
+/// Everything the editor window needs, owned by the window thread.
+pub struct Editor {
+    params: Arc<Params>,
+    param_changes: Arc<RingBuffer<ParamChange>>,
+    visualizer: Arc<VisualizerFeed>,
+    /// `None` while the previous editor still reads the feed
+    reader: Option<VisualizerReader>,
+    /// Latest output, oldest first
+    samples: Vec<f32>,
+    playheads: Playheads,
+}
+
+impl Editor {
+    pub fn new(
+        params: Arc<Params>,
+        param_changes: Arc<RingBuffer<ParamChange>>,
+        visualizer: Arc<VisualizerFeed>,
+    ) -> Self {
+        Self {
+            params,
+            param_changes,
+            reader: VisualizerFeed::reader(&visualizer),
+            visualizer,
+            samples: Vec::with_capacity(2 * SPECTRUM_LENGTH),
+            playheads: Playheads::default(),
+        }
+    }
+
+    pub fn update(&mut self, context: &Context) {
+        if self.reader.is_none() {
+            self.reader = VisualizerFeed::reader(&self.visualizer);
+        }
+        if let Some(reader) = &mut self.reader {
+            reader.pop_samples(&mut self.samples, SPECTRUM_LENGTH);
+            self.playheads.update(reader);
+        }
+        // The visualizers move even while nobody touches the editor
+        context.request_repaint();
+
+        CentralPanel::default().show(context, |ui| {
+            ui.heading("Schoffhauzer Synth");
+            ui.separator();
+            ui.horizontal(|ui| {
+                self.knob(ui, Params::VOLUME, "Volume");
+                self.knob(ui, Params::HF_ROLLOFF, "HF Rolloff");
+                ui.add_space(16.0);
+                let adsr = self.params.get_adsr().map(|it| it.modulated());
+                envelope_view(ui, vec2(ui.available_width(), 80.0), &adsr, self.playheads.iter().copied());
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                let adsr = Params::ADSR;
+                self.knob(ui, adsr.attack_duration, "Attack");
+                self.knob(ui, adsr.attack_power, "Attack Power");
+                self.knob(ui, adsr.decay_duration, "Decay");
+                self.knob(ui, adsr.decay_power, "Decay Power");
+                self.knob(ui, adsr.sustain, "Sustain");
+                self.knob(ui, adsr.release_duration, "Release");
+                self.knob(ui, adsr.release_power, "Release Power");
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                let size = vec2((ui.available_width() - ui.spacing().item_spacing.x) * 0.5, ui.available_height());
+                scope_view(ui, size, &self.samples);
+                spectrum_view(ui, size, &self.samples, self.visualizer.sample_rate());
+            });
+        });
+    }
+
+    fn knob(&self, ui: &mut Ui, info: &ParamInfo<'static>, label: &str) {
+        let Some(mut value) = self.params.get_value(info.id) else {
+            return;
+        };
+        let mut text = String::new();
+        if Params::value_to_text(info.id, value, &mut text).is_err() {
+            text = format!("{value:.2}");
+        }
+
+        let response = ui.add(Knob::new(info, &mut value, label, &text));
+        // A double click resets the value in one go, so it gets a gesture of its own
+        if response.drag_started() || response.double_clicked() {
+            self.report(ParamChange::GestureBegin(info.id));
+        }
+        if response.changed() {
+            let event = ParamValueEvent::new(0, info.id, Pckn::match_all(), value, Cookie::empty());
+            self.params.handle_param_value_event(&event);
+            self.report(ParamChange::Value(info.id, value));
+        }
+        if response.drag_stopped() || response.double_clicked() {
+            self.report(ParamChange::GestureEnd(info.id));
+        }
+    }
+
+    fn report(&self, change: ParamChange) {
+        // Only automation recording misses out if the audio thread falls this far behind,
+        // or if the next editor is reporting at the same time while this one closes
+        if let Some(mut producer) = self.param_changes.producer() {
+            let _ = producer.push(change);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::gui::visualizer::Playhead;
+use crate::utils::envelope::{ADSR, ADSRPhase};
+use egui_baseview::egui::{Pos2, Sense, Stroke, Ui, Vec2, pos2};
+
+/// Share of the width given to the sustain, which has no duration of its own
+const SUSTAIN_SHARE: f32 = 0.2;
+const POINTS_PER_PHASE: usize = 32;
+const CURVE_WIDTH: f32 = 2.0;
+const PHASES: [ADSRPhase; 4] = [ADSRPhase::Attack, ADSRPhase::Decay, ADSRPhase::Sustain, ADSRPhase::Release];
+
+/// Horizontal layout of the phases, as fractions of the width.
+struct Layout {
+    /// Start and width of each of [`PHASES`]
+    spans: [(f32, f32); 4],
+}
+
+impl Layout {
+    fn new(adsr: &ADSR<f32>) -> Self {
+        let durations = PHASES.map(|phase| adsr.duration(phase).map_or(0.0, |duration| duration.max(0.0)));
+        let total: f32 = durations.iter().sum();
+        let mut start = 0.0;
+        let spans = PHASES.map(|phase| {
+            let width = match phase {
+                ADSRPhase::Sustain => SUSTAIN_SHARE,
+                // With every duration at zero the timed phases share the width evenly
+                _ if total == 0.0 => (1.0 - SUSTAIN_SHARE) / 3.0,
+                _ => (1.0 - SUSTAIN_SHARE) * durations[phase as usize] / total,
+            };
+            let span = (start, width);
+            start += width;
+            span
+        });
+        Self { spans }
+    }
+
+    fn x(&self, phase: ADSRPhase, progress: f32) -> f32 {
+        let (start, width) = self.spans[phase as usize];
+        start + width * progress.clamp(0.0, 1.0)
+    }
+}
+
+/// Level at the start of each phase of a note held through its attack and decay.
+fn start_level(adsr: &ADSR<f32>, phase: ADSRPhase) -> f32 {
+    match phase {
+        ADSRPhase::Attack => 0.0,
+        ADSRPhase::Decay => 1.0,
+        ADSRPhase::Sustain | ADSRPhase::Release => adsr.sustain,
+    }
+}
+
+/// Draws the shape of `adsr` with a dot where each voice is along it.
+pub fn envelope_view(ui: &mut Ui, size: Vec2, adsr: &ADSR<f32>, playheads: impl Iterator<Item = Playhead>) {
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let rect = rect.shrink(4.0);
+    // The sustain may go above full scale
+    let max_level = adsr.sustain.max(1.0);
+    let to_screen = |x: f32, level: f32| -> Pos2 {
+        pos2(rect.left() + rect.width() * x, rect.bottom() - rect.height() * level / max_level)
+    };
+
+    let layout = Layout::new(adsr);
+    let points = PHASES
+        .iter()
+        .flat_map(|&phase| {
+            let start_level = start_level(adsr, phase);
+            let layout = &layout;
+            (0..=POINTS_PER_PHASE).map(move |i| {
+                let progress = i as f32 / POINTS_PER_PHASE as f32;
+                let level = adsr.level(phase, start_level, progress);
+                to_screen(layout.x(phase, progress), level)
+            })
+        })
+        .collect();
+    painter.line(points, Stroke::new(CURVE_WIDTH, visuals.selection.bg_fill));
+
+    for (start, _) in &layout.spans[1..] {
+        painter.vline(to_screen(*start, 0.0).x, rect.y_range(), visuals.widgets.noninteractive.bg_stroke);
+    }
+    for playhead in playheads {
+        // The sustain has no progress, so voices holding it sit in the middle
+        let progress = match playhead.phase {
+            ADSRPhase::Sustain => 0.5,
+            _ => playhead.progress,
+        };
+        let position = to_screen(layout.x(playhead.phase, progress), playhead.level);
+        painter.circle_filled(position, 3.0, visuals.strong_text_color());
+    }
+}
+
@@ -0,0 +1,80 @@
+use clack_extensions::params::ParamInfo;
+use egui_baseview::egui::{Align2, FontId, Pos2, Rect, Response, Sense, Stroke, Ui, Vec2, Widget, pos2, vec2};
+use std::f32::consts::PI;
+
+const RADIUS: f32 = 20.0;
+/// Angle of the minimum, clockwise from the right since y points down
+const START_ANGLE: f32 = 0.75 * PI;
+const SWEEP: f32 = 1.5 * PI;
+const TRACK_WIDTH: f32 = 4.0;
+/// Fraction of the range covered by dragging one point
+const DRAG_SPEED: f64 = 0.005;
+/// While holding shift
+const FINE_DRAG_SPEED: f64 = 0.0005;
+
+/// A rotary control for one parameter, dragged vertically and reset to the default by double clicking.
+/// Marks the response changed whenever `value` moves.
+pub struct Knob<'a> {
+    info: &'a ParamInfo<'a>,
+    value: &'a mut f64,
+    label: &'a str,
+    text: &'a str,
+}
+
+impl<'a> Knob<'a> {
+    pub fn new(info: &'a ParamInfo<'a>, value: &'a mut f64, label: &'a str, text: &'a str) -> Self {
+        Self { info, value, label, text }
+    }
+
+    fn normalized(&self) -> f32 {
+        let range = self.info.max_value - self.info.min_value;
+        ((*self.value - self.info.min_value) / range) as f32
+    }
+}
+
+fn arc(ui: &Ui, center: Pos2, from: f32, to: f32, stroke: Stroke) {
+    let segments = ((to - from).abs() / SWEEP * 32.0).ceil().max(1.0) as usize;
+    let points = (0..=segments)
+        .map(|i| {
+            let angle = from + (to - from) * i as f32 / segments as f32;
+            center + RADIUS * Vec2::angled(angle)
+        })
+        .collect();
+    ui.painter().line(points, stroke);
+}
+
+impl Widget for Knob<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let size = vec2(2.0 * RADIUS + 40.0, 2.0 * RADIUS + 40.0);
+        let (rect, mut response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+        let (min, max) = (self.info.min_value, self.info.max_value);
+
+        if response.double_clicked() {
+            *self.value = self.info.default_value;
+            response.mark_changed();
+        } else if response.dragged() {
+            let speed = if ui.input(|input| input.modifiers.shift) { FINE_DRAG_SPEED } else { DRAG_SPEED };
+            let value = (*self.value - response.drag_delta().y as f64 * speed * (max - min)).clamp(min, max);
+            if value != *self.value {
+                *self.value = value;
+                response.mark_changed();
+            }
+        }
+
+        if ui.is_rect_visible(rect) {
+            let visuals = ui.style().interact(&response);
+            let center = pos2(rect.center().x, rect.top() + RADIUS + 4.0);
+            let angle = START_ANGLE + SWEEP * self.normalized();
+            arc(ui, center, START_ANGLE, START_ANGLE + SWEEP, Stroke::new(TRACK_WIDTH, ui.visuals().extreme_bg_color));
+            arc(ui, center, START_ANGLE, angle, Stroke::new(TRACK_WIDTH, ui.visuals().selection.bg_fill));
+            ui.painter().line_segment([center, center + RADIUS * 0.8 * Vec2::angled(angle)], visuals.fg_stroke);
+
+            let text_rect = Rect::from_min_max(pos2(rect.left(), center.y + RADIUS + 2.0), rect.max);
+            let font = FontId::proportional(11.0);
+            ui.painter().text(text_rect.center_top(), Align2::CENTER_TOP, self.label, font.clone(), visuals.text_color());
+            ui.painter().text(text_rect.center_bottom(), Align2::CENTER_BOTTOM, self.text, font, ui.visuals().weak_text_color());
+        }
+
+        response
+    }
+}
@@ -0,0 +1,233 @@
+mod editor;
+mod envelope_view;
+mod knob;
+mod scope_view;
+mod visualizer;
+
+#[cfg(test)]
+mod tests;
+
+pub use visualizer::VisualizerFeed;
+
+use crate::SchoffhauzerSynthPluginMainThread;
+use crate::gui::editor::Editor;
+use baseview::gl::GlConfig;
+use baseview::{Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
+use clack_extensions::gui::{
+    GuiApiType, GuiConfiguration, GuiResizeHints, GuiSize, PluginGuiImpl, Window,
+};
+use clack_plugin::events::Pckn;
+use clack_plugin::events::event_types::{
+    ParamGestureBeginEvent, ParamGestureEndEvent, ParamValueEvent,
+};
+use clack_extensions::timer::{PluginTimerImpl, TimerId};
+use clack_plugin::prelude::{ClapId, OutputEvents, PluginError};
+use clack_plugin::utils::Cookie;
+use egui_baseview::EguiWindow;
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
+use raw_window_handle_05 as rwh_05;
+use std::ffi::{CStr, c_void};
+use std::sync::atomic::Ordering;
+
+/// In logical pixels
+const EDITOR_SIZE: (u32, u32) = (640, 440);
+/// How often editor changes are checked for while the processor sleeps, in milliseconds
+const FLUSH_TIMER_PERIOD: u32 = 30;
+
+/// A parameter edit made in the editor, which the host needs to hear about to record automation.
+#[derive_aliases::derive(..Copy, Debug)]
+pub enum ParamChange {
+    GestureBegin(ClapId),
+    Value(ClapId, f64),
+    GestureEnd(ClapId),
+}
+
+impl ParamChange {
+    /// Reports the change to the host, the value itself has already been applied to the parameters.
+    pub fn push_to(self, output: &mut OutputEvents) {
+        // The host dropping the event only affects automation recording
+        let _ = match self {
+            ParamChange::GestureBegin(param_id) => output.try_push(ParamGestureBeginEvent::new(0, param_id)),
+            ParamChange::Value(param_id, value) => {
+                output.try_push(ParamValueEvent::new(0, param_id, Pckn::match_all(), value, Cookie::empty()))
+            }
+            ParamChange::GestureEnd(param_id) => output.try_push(ParamGestureEndEvent::new(0, param_id)),
+        };
+    }
+}
+
+/// The open editor window, if any, and the scale the host asked for.
+pub struct GuiState {
+    window: Option<WindowHandle>,
+    scale: f64,
+}
+
+impl Default for GuiState {
+    fn default() -> Self {
+        Self { window: None, scale: 1.0 }
+    }
+}
+
+/// The host window in the `raw-window-handle` 0.5 form baseview expects.
+struct ParentWindow(rwh_05::RawWindowHandle);
+
+// Safety: the handle comes straight from the host and stays valid until `PluginGuiImpl::destroy`
+unsafe impl rwh_05::HasRawWindowHandle for ParentWindow {
+    fn raw_window_handle(&self) -> rwh_05::RawWindowHandle {
+        self.0
+    }
+}
+
+impl TryFrom<Window<'_>> for ParentWindow {
+    type Error = PluginError;
+
+    fn try_from(window: Window<'_>) -> Result<Self, PluginError> {
+        let handle = window
+            .window_handle()
+            .map_err(|_| PluginError::Message("Invalid parent window"))?;
+        Ok(Self(match handle.as_raw() {
+            RawWindowHandle::Win32(handle) => {
+                let mut converted = rwh_05::Win32WindowHandle::empty();
+                converted.hwnd = handle.hwnd.get() as *mut c_void;
+                rwh_05::RawWindowHandle::Win32(converted)
+            }
+            RawWindowHandle::AppKit(handle) => {
+                let mut converted = rwh_05::AppKitWindowHandle::empty();
+                converted.ns_view = handle.ns_view.as_ptr();
+                rwh_05::RawWindowHandle::AppKit(converted)
+            }
+            RawWindowHandle::Xlib(handle) => {
+                let mut converted = rwh_05::XlibWindowHandle::empty();
+                converted.window = handle.window;
+                rwh_05::RawWindowHandle::Xlib(converted)
+            }
+            RawWindowHandle::Xcb(handle) => {
+                let mut converted = rwh_05::XcbWindowHandle::empty();
+                converted.window = handle.window.get();
+                rwh_05::RawWindowHandle::Xcb(converted)
+            }
+            _ => return Err(PluginError::Message("Unsupported parent window")),
+        }))
+    }
+}
+
+impl<'a> PluginGuiImpl for SchoffhauzerSynthPluginMainThread<'a> {
+    fn is_api_supported(&mut self, configuration: GuiConfiguration) -> bool {
+        !configuration.is_floating
+            && GuiApiType::default_for_current_platform() == Some(configuration.api_type)
+    }
+
+    fn get_preferred_api(&mut self) -> Option<GuiConfiguration<'_>> {
+        Some(GuiConfiguration {
+            api_type: GuiApiType::default_for_current_platform()?,
+            is_floating: false,
+        })
+    }
+
+    fn create(&mut self, configuration: GuiConfiguration) -> Result<(), PluginError> {
+        if !self.is_api_supported(configuration) {
+            return Err(PluginError::Message("Unsupported GUI configuration"));
+        }
+        // The window itself is opened once the host hands us a parent
+        Ok(())
+    }
+
+    fn destroy(&mut self) {
+        if let Some(mut window) = self.gui.window.take() {
+            window.close();
+        }
+        if let (Some(host_timer), Some(timer_id)) = (self.host_timer, self.flush_timer.take()) {
+            let _ = host_timer.unregister_timer(&mut self.host, timer_id);
+        }
+    }
+
+    fn set_scale(&mut self, scale: f64) -> Result<(), PluginError> {
+        self.gui.scale = scale;
+        Ok(())
+    }
+
+    fn get_size(&mut self) -> Option<GuiSize> {
+        let (width, height) = EDITOR_SIZE;
+        Some(GuiSize {
+            width: (width as f64 * self.gui.scale).round() as u32,
+            height: (height as f64 * self.gui.scale).round() as u32,
+        })
+    }
+
+    fn can_resize(&mut self) -> bool {
+        false
+    }
+
+    fn get_resize_hints(&mut self) -> Option<GuiResizeHints> {
+        None
+    }
+
+    fn adjust_size(&mut self, _size: GuiSize) -> Option<GuiSize> {
+        self.get_size()
+    }
+
+    fn set_size(&mut self, size: GuiSize) -> Result<(), PluginError> {
+        let expected = self.get_size();
+        if expected.is_some_and(|expected| expected.width == size.width && expected.height == size.height) {
+            Ok(())
+        } else {
+            Err(PluginError::Message("The editor can't be resized"))
+        }
+    }
+
+    fn set_parent(&mut self, window: Window) -> Result<(), PluginError> {
+        self.destroy();
+        let parent = ParentWindow::try_from(window)?;
+        let (width, height) = EDITOR_SIZE;
+        let editor = Editor::new(
+            self.shared.params.clone(),
+            self.shared.param_changes.clone(),
+            self.shared.visualizer.clone(),
+        );
+        self.gui.window = Some(EguiWindow::open_parented(
+            &parent,
+            WindowOpenOptions {
+                title: String::from("Schoffhauzer Synth"),
+                size: Size::new(width as f64, height as f64),
+                scale: WindowScalePolicy::ScaleFactor(self.gui.scale),
+                gl_config: Some(GlConfig::default()),
+            },
+            Default::default(),
+            editor,
+            |_context, _queue, _editor| {},
+            |context, _queue, editor| editor.update(context),
+        ));
+        // Without timers, editor changes reach the host whenever the processor next wakes up
+        if let Some(host_timer) = self.host_timer {
+            self.flush_timer = host_timer.register_timer(&mut self.host, FLUSH_TIMER_PERIOD).ok();
+        }
+        Ok(())
+    }
+
+    fn set_transient(&mut self, _window: Window) -> Result<(), PluginError> {
+        Err(PluginError::Message("Floating windows aren't supported"))
+    }
+
+    fn suggest_title(&mut self, _title: &CStr) {}
+
+    fn show(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    fn hide(&mut self) -> Result<(), PluginError> {
+        Ok(())
+    }
+}
+
+impl<'a> PluginTimerImpl for SchoffhauzerSynthPluginMainThread<'a> {
+    fn on_timer(&mut self, timer_id: TimerId) {
+        if self.flush_timer != Some(timer_id) {
+            return;
+        }
+        // An awake processor reports the changes itself
+        let is_stuck = !self.shared.param_changes.is_empty() && !self.shared.processor_awake.load(Ordering::Relaxed);
+        if is_stuck && let Some(host_params) = self.host_params {
+            host_params.request_flush(&self.host.shared());
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::utils::db::DB;
+use crate::utils::fft::magnitude_spectrum;
+use egui_baseview::egui::{Sense, Stroke, Ui, Vec2, pos2};
+
+/// In samples, how much of the output the oscilloscope shows
+const SCOPE_LENGTH: usize = 1024;
+/// In samples, a power of two
+pub const SPECTRUM_LENGTH: usize = 4096;
+/// In Hz
+const SPECTRUM_FREQUENCIES: (f32, f32) = (20.0, 20000.0);
+/// In dB
+const SPECTRUM_FLOOR: f32 = -90.0;
+const LINE_WIDTH: f32 = 1.5;
+
+/// Draws the last [`SCOPE_LENGTH`] samples of `samples`, starting at a rising zero crossing when there is one
+/// so periodic waveforms stand still.
+pub fn scope_view(ui: &mut Ui, size: Vec2, samples: &[f32]) {
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    painter.hline(rect.x_range(), rect.center().y, visuals.widgets.noninteractive.bg_stroke);
+    if samples.len() < SCOPE_LENGTH {
+        return;
+    }
+
+    let search = &samples[..samples.len() - SCOPE_LENGTH];
+    let trigger = search
+        .windows(2)
+        .rposition(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+        .map_or(search.len(), |index| index + 1);
+    let points = samples[trigger..trigger + SCOPE_LENGTH]
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| {
+            let x = rect.left() + rect.width() * i as f32 / (SCOPE_LENGTH - 1) as f32;
+            pos2(x, rect.center().y - rect.height() * 0.5 * sample.clamp(-1.0, 1.0))
+        })
+        .collect();
+    painter.line(points, Stroke::new(LINE_WIDTH, visuals.selection.bg_fill));
+}
+
+/// Draws the magnitude spectrum of the last [`SPECTRUM_LENGTH`] samples of `samples` on a logarithmic frequency axis.
+pub fn spectrum_view(ui: &mut Ui, size: Vec2, samples: &[f32], sample_rate: f32) {
+    let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+    if !ui.is_rect_visible(rect) {
+        return;
+    }
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+    if samples.len() < SPECTRUM_LENGTH {
+        return;
+    }
+
+    let (low, high) = SPECTRUM_FREQUENCIES;
+    let high = high.min(sample_rate * 0.5);
+    let octaves = (high / low).log2();
+    let spectrum = magnitude_spectrum(&samples[samples.len() - SPECTRUM_LENGTH..]);
+    let bin_width = sample_rate / SPECTRUM_LENGTH as f32;
+    let points = spectrum
+        .iter()
+        .enumerate()
+        .filter_map(|(bin, magnitude)| {
+            let freq = bin as f32 * bin_width;
+            if freq < low || freq > high {
+                return None;
+            }
+            let x = (freq / low).log2() / octaves;
+            let level = (DB::from_linear(*magnitude).db().max(SPECTRUM_FLOOR) / SPECTRUM_FLOOR).min(1.0);
+            Some(pos2(rect.left() + rect.width() * x, rect.top() + rect.height() * level))
+        })
+        .collect();
+    painter.line(points, Stroke::new(LINE_WIDTH, visuals.selection.bg_fill));
+}
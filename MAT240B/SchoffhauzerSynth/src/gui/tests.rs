@@ -0,0 +1,28 @@
+//! Tests of the editor's view of the audio thread.
+
+use crate::gui::visualizer::VisualizerFeed;
+use std::sync::Arc;
+
+#[test]
+fn visualizer_feed_has_one_reader_at_a_time() {
+    let feed = Arc::new(VisualizerFeed::default());
+    let reader = VisualizerFeed::reader(&feed).unwrap();
+    // The next editor opens before the previous one closes
+    assert!(VisualizerFeed::reader(&feed).is_none());
+    drop(reader);
+    assert!(VisualizerFeed::reader(&feed).is_some());
+}
+
+#[test]
+fn visualizer_feed_is_only_fed_while_read() {
+    let feed = Arc::new(VisualizerFeed::default());
+    feed.push(&[1.0; 4], &[0.0; 4], std::iter::empty());
+    let mut reader = VisualizerFeed::reader(&feed).unwrap();
+    let mut samples = Vec::new();
+    reader.pop_samples(&mut samples, 16);
+    assert!(samples.is_empty());
+
+    feed.push(&[1.0; 4], &[0.0; 4], std::iter::empty());
+    reader.pop_samples(&mut samples, 16);
+    assert_eq!(samples, [0.5; 4]);
+}
@@ -0,0 +1,139 @@
+use crate::utils::envelope::{ADSRInstance, ADSRPhase};
+use crate::utils::ring_buffer::RingBuffer;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Where one voice is along its amplitude envelope.
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct Playhead {
+    pub phase: ADSRPhase,
+    /// Fraction of `phase` that has passed
+    pub progress: f32,
+    pub level: f32,
+}
+
+/// What the audio thread shows the editor, only fed while an editor holds its [`VisualizerReader`].
+pub struct VisualizerFeed {
+    /// Whether a reader exists, editors can briefly overlap while one closes and the next opens
+    /// but only one of them reads at a time
+    reader_claimed: AtomicBool,
+    /// Bits of the `f32` sample rate
+    sample_rate: AtomicU32,
+    /// Mono mix of the output
+    samples: RingBuffer<f32>,
+    /// The playheads of each block followed by `None`
+    playheads: RingBuffer<Option<Playhead>>,
+}
+
+impl Default for VisualizerFeed {
+    fn default() -> Self {
+        Self {
+            reader_claimed: AtomicBool::new(false),
+            sample_rate: AtomicU32::new(44100.0f32.to_bits()),
+            samples: RingBuffer::with_capacity(Self::SAMPLE_CAPACITY),
+            playheads: RingBuffer::with_capacity(Self::PLAYHEAD_CAPACITY),
+        }
+    }
+}
+
+impl VisualizerFeed {
+    /// A bit under half a second at 44.1 kHz, plenty between two editor frames
+    const SAMPLE_CAPACITY: usize = 1 << 14;
+    const PLAYHEAD_CAPACITY: usize = 1024;
+
+    /// The only handle that reads the feed, `None` until the previous editor has dropped its reader.
+    pub fn reader(feed: &Arc<Self>) -> Option<VisualizerReader> {
+        feed.reader_claimed
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        Some(VisualizerReader { feed: feed.clone() })
+    }
+
+    pub fn set_sample_rate(&self, sample_rate: f32) {
+        self.sample_rate.store(sample_rate.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        f32::from_bits(self.sample_rate.load(Ordering::Relaxed))
+    }
+
+    /// Called by the audio thread after each block, drops whatever doesn't fit until the editor catches up.
+    pub fn push<'a>(&self, left: &[f32], right: &[f32], envelopes: impl Iterator<Item = &'a ADSRInstance> + Clone) {
+        if !self.reader_claimed.load(Ordering::Relaxed) {
+            return;
+        }
+        let (Some(mut samples), Some(mut playheads)) = (self.samples.producer(), self.playheads.producer()) else {
+            return;
+        };
+        for (left, right) in left.iter().zip(right) {
+            if samples.push((left + right) * 0.5).is_err() {
+                break;
+            }
+        }
+        // Only whole blocks, so the editor never shows half of the voices
+        if playheads.free_capacity() > envelopes.clone().count() {
+            for envelope in envelopes {
+                if let Some(phase) = envelope.phase() {
+                    let _ = playheads.push(Some(Playhead {
+                        phase,
+                        progress: envelope.progress(),
+                        level: envelope.current_level(),
+                    }));
+                }
+            }
+            let _ = playheads.push(None);
+        }
+    }
+
+}
+
+/// The editor's claim on a [`VisualizerFeed`], released when dropped.
+pub struct VisualizerReader {
+    feed: Arc<VisualizerFeed>,
+}
+
+impl VisualizerReader {
+    /// Moves the samples pushed since the last call to the end of `samples`, keeping at most `keep` samples.
+    pub fn pop_samples(&mut self, samples: &mut Vec<f32>, keep: usize) {
+        if let Some(mut consumer) = self.feed.samples.consumer() {
+            samples.extend(consumer.drain());
+        }
+        let excess = samples.len().saturating_sub(keep);
+        samples.drain(..excess);
+    }
+}
+
+impl Drop for VisualizerReader {
+    fn drop(&mut self) {
+        self.feed.reader_claimed.store(false, Ordering::Release);
+    }
+}
+
+/// The playheads of the last whole block, as seen by the editor.
+#[derive_aliases::derive(Debug, Default)]
+pub struct Playheads {
+    current: Vec<Playhead>,
+    /// The block still being received
+    incoming: Vec<Playhead>,
+}
+
+impl Playheads {
+    pub fn update(&mut self, reader: &mut VisualizerReader) {
+        let Some(mut consumer) = reader.feed.playheads.consumer() else {
+            return;
+        };
+        while let Some(playhead) = consumer.pop() {
+            match playhead {
+                Some(playhead) => self.incoming.push(playhead),
+                None => {
+                    std::mem::swap(&mut self.current, &mut self.incoming);
+                    self.incoming.clear();
+                }
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Playhead> {
+        self.current.iter()
+    }
+}
@@ -1,28 +1,35 @@
-#![feature(linked_list_cursors)]
-#![feature(step_trait)]
-#![feature(new_range_api)]
-
 mod derive_alias;
+mod gui;
+pub mod offline;
 mod params;
 mod save_state;
 mod synth;
 mod utils;
 
+#[cfg(test)]
+mod tests;
+
+use crate::gui::{GuiState, ParamChange, VisualizerFeed};
 use crate::params::SchoffhauzerSynthPluginParams;
 use clack_extensions::audio_ports::{
     AudioPortFlags, AudioPortInfo, AudioPortInfoWriter, AudioPortType, PluginAudioPorts,
     PluginAudioPortsImpl,
 };
+use clack_extensions::gui::PluginGui;
 use clack_extensions::note_ports::{
     NoteDialect, NoteDialects, NotePortInfo, NotePortInfoWriter, PluginNotePorts,
     PluginNotePortsImpl,
 };
-use clack_extensions::params::PluginParams;
+use clack_extensions::params::{HostParams, PluginParams};
 use clack_extensions::state::PluginState;
-use clack_plugin::events::spaces::CoreEventSpace;
-use clack_plugin::plugin::features::{INSTRUMENT, MONO, SYNTHESIZER};
+use clack_extensions::timer::{HostTimer, PluginTimer, TimerId};
+use clack_plugin::plugin::features::{INSTRUMENT, STEREO, SYNTHESIZER};
 use clack_plugin::prelude::*;
 use crate::synth::poly_synth::PolySynth;
+use crate::utils::alloc_guard::forbid_alloc;
+use crate::utils::ring_buffer::RingBuffer;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub struct SchoffhauzerSynthPlugin;
 
@@ -39,59 +46,47 @@ impl Plugin for SchoffhauzerSynthPlugin {
             .register::<PluginAudioPorts>()
             .register::<PluginNotePorts>()
             .register::<PluginParams>()
-            .register::<PluginState>();
+            .register::<PluginState>()
+            .register::<PluginGui>()
+            .register::<PluginTimer>();
     }
 }
 
 impl DefaultPluginFactory for SchoffhauzerSynthPlugin {
     fn get_descriptor() -> PluginDescriptor {
         PluginDescriptor::new("dev.shblock.schoffhauzer_synth", "Schoffhauzer Synth")
-            .with_features([SYNTHESIZER, MONO, INSTRUMENT])
+            .with_features([SYNTHESIZER, STEREO, INSTRUMENT])
     }
 
     fn new_shared(_host: HostSharedHandle<'_>) -> Result<Self::Shared<'_>, PluginError> {
         Ok(SchoffhauzerSynthShared {
-            params: SchoffhauzerSynthPluginParams::default(),
+            params: Arc::new(SchoffhauzerSynthPluginParams::default()),
+            param_changes: Arc::new(RingBuffer::with_capacity(SchoffhauzerSynthShared::PARAM_CHANGE_CAPACITY)),
+            visualizer: Arc::new(VisualizerFeed::default()),
+            processor_awake: AtomicBool::new(false),
         })
     }
 
     fn new_main_thread<'a>(
-        _host: HostMainThreadHandle<'a>,
+        host: HostMainThreadHandle<'a>,
         shared: &'a Self::Shared<'a>,
     ) -> Result<Self::MainThread<'a>, PluginError> {
-        Ok(SchoffhauzerSynthPluginMainThread { shared })
+        Ok(SchoffhauzerSynthPluginMainThread {
+            shared,
+            host_params: host.shared().get_extension(),
+            host_timer: host.shared().get_extension(),
+            host,
+            gui: GuiState::default(),
+            flush_timer: None,
+        })
     }
 }
 
 pub struct SchoffhauzerSynthAudioProcessor<'a> {
     shared: &'a SchoffhauzerSynthShared,
     synth: PolySynth,
-}
-
-impl<'a> SchoffhauzerSynthAudioProcessor<'a> {
-    fn handle_event(&mut self, event: &UnknownEvent) {
-        match event.as_core_event() {
-            Some(CoreEventSpace::NoteOn(event)) => self.synth.handle_note_on_event(event, &self.shared.params),
-            Some(CoreEventSpace::NoteOff(event)) => self.synth.handle_note_off_event(event),
-            Some(CoreEventSpace::NoteChoke(event)) => self.synth.handle_note_choke_event(event),
-            Some(CoreEventSpace::ParamValue(event)) => {
-                if event.pckn().matches_all() {
-                    self.shared.params.handle_param_value_event(event);
-                } else {
-                    self.synth.handle_param_value_event(event);
-                }
-            }
-            Some(CoreEventSpace::ParamMod(event)) => {
-                if event.pckn().matches_all() {
-                    self.shared.params.handle_param_mod_event(event);
-                } else {
-                    self.synth.handle_param_mod_event(event);
-                }
-            }
-            // Some(CoreEventSpace::NoteExpression(event)) => {}
-            _ => {}
-        }
-    }
+    /// Stands in for the right channel if the host only gives us one
+    mono_scratch: Vec<f32>,
 }
 
 impl<'a> PluginAudioProcessor<'a, SchoffhauzerSynthShared, SchoffhauzerSynthPluginMainThread<'a>>
@@ -103,69 +98,127 @@ impl<'a> PluginAudioProcessor<'a, SchoffhauzerSynthShared, SchoffhauzerSynthPlug
         shared: &'a SchoffhauzerSynthShared,
         audio_config: PluginAudioConfiguration,
     ) -> Result<Self, PluginError> {
+        shared.visualizer.set_sample_rate(audio_config.sample_rate as f32);
         Ok(Self {
             shared,
             synth: PolySynth::new(audio_config.sample_rate as f32),
+            mono_scratch: vec![0.0; audio_config.max_frames_count as usize],
         })
     }
 
     fn process(
         &mut self,
-        _process: Process,
+        process: Process,
         mut audio: Audio,
         events: Events,
     ) -> Result<ProcessStatus, PluginError> {
-        let mut output_port = audio
-            .output_port(0)
-            .ok_or(PluginError::Message("No output port found"))?;
-
-        let mut output_channels = output_port
-            .channels()?
-            .into_f32()
-            .ok_or(PluginError::Message("Expected f32 output"))?;
-
-        let output_buffer = output_channels
-            .channel_mut(0)
-            .ok_or(PluginError::Message("Expected at least one channel"))?;
-        output_buffer.fill(0.0);
-
-        for event_batch in events.input.batch() {
-            event_batch
-                .events()
-                .for_each(|event| self.handle_event(event));
-
-            self.synth.synth(
-                &mut output_buffer[event_batch.sample_bounds()],
-                &self.shared.params,
-            );
-        }
+        self.shared.processor_awake.store(true, Ordering::Relaxed);
+        forbid_alloc(|| {
+            let mut output_port = audio
+                .output_port(0)
+                .ok_or(PluginError::Message("No output port found"))?;
 
-        // If somehow the host didn't give us a mono output, we copy the output to all channels
-        if output_channels.channel_count() > 1 {
-            let (first_channel, other_channels) = output_channels.split_at_mut(1);
-            let first_channel = first_channel.channel(0).unwrap();
+            let mut output_channels = output_port
+                .channels()?
+                .into_f32()
+                .ok_or(PluginError::Message("Expected f32 output"))?;
 
-            for other_channel in other_channels {
-                other_channel.copy_from_slice(first_channel);
+            let is_mono = output_channels.channel_count() < 2;
+            let (mut left_channels, mut right_channels) = output_channels.split_at_mut(1);
+            let left = left_channels
+                .channel_mut(0)
+                .ok_or(PluginError::Message("Expected at least one channel"))?;
+            let right = match right_channels.channel_mut(0) {
+                Some(right) => right,
+                None => self
+                    .mono_scratch
+                    .get_mut(..left.len())
+                    .ok_or(PluginError::Message("Block larger than max_frames_count"))?,
+            };
+            left.fill(0.0);
+            right.fill(0.0);
+
+            if let Some(transport) = process.transport {
+                self.synth.handle_transport_event(transport);
             }
-        }
 
-        if self.synth.is_busy() {
-            Ok(ProcessStatus::Continue)
-        } else {
-            Ok(ProcessStatus::Sleep)
-        }
+            for event_batch in events.input.batch() {
+                event_batch
+                    .events()
+                    .for_each(|event| self.synth.handle_event(event, &self.shared.params));
+
+                let bounds = event_batch.sample_bounds();
+                self.synth.synth(
+                    &mut left[bounds],
+                    &mut right[bounds],
+                    &self.shared.params,
+                );
+            }
+
+            self.shared.visualizer.push(left, right, self.synth.envelopes());
+
+            // If somehow the host didn't give us a stereo output, we downmix to the single channel
+            if is_mono {
+                for (left, right) in left.iter_mut().zip(right.iter()) {
+                    *left = (*left + *right) * 0.5;
+                }
+            }
+
+            // Any channels past the first two are left silent
+            for index in 1..right_channels.channel_count() {
+                if let Some(channel) = right_channels.channel_mut(index) {
+                    channel.fill(0.0);
+                }
+            }
+
+            // Left for the next block if a flush is draining them right now
+            if let Some(mut changes) = self.shared.param_changes.consumer() {
+                for change in changes.drain() {
+                    change.push_to(events.output);
+                }
+            }
+
+            if self.synth.is_busy() {
+                Ok(ProcessStatus::Continue)
+            } else {
+                self.shared.processor_awake.store(false, Ordering::Relaxed);
+                Ok(ProcessStatus::Sleep)
+            }
+        })
+    }
+
+    fn deactivate(self, _main_thread: &mut SchoffhauzerSynthPluginMainThread<'a>) {
+        self.shared.processor_awake.store(false, Ordering::Relaxed);
+    }
+
+    fn stop_processing(&mut self) {
+        self.shared.processor_awake.store(false, Ordering::Relaxed);
     }
 }
 
 pub struct SchoffhauzerSynthShared {
-    params: SchoffhauzerSynthPluginParams,
+    params: Arc<SchoffhauzerSynthPluginParams>,
+    /// Edits made in the editor, waiting to be reported to the host
+    param_changes: Arc<RingBuffer<ParamChange>>,
+    visualizer: Arc<VisualizerFeed>,
+    /// Whether `process` will be called again without the host being asked to
+    processor_awake: AtomicBool,
+}
+
+impl SchoffhauzerSynthShared {
+    const PARAM_CHANGE_CAPACITY: usize = 1024;
 }
 
 impl PluginShared<'_> for SchoffhauzerSynthShared {}
 
 pub struct SchoffhauzerSynthPluginMainThread<'a> {
     shared: &'a SchoffhauzerSynthShared,
+    host: HostMainThreadHandle<'a>,
+    host_params: Option<HostParams>,
+    host_timer: Option<HostTimer>,
+    gui: GuiState,
+    /// Checks for editor changes the sleeping processor won't pick up, while the editor is open
+    flush_timer: Option<TimerId>,
 }
 
 impl<'a> PluginMainThread<'a, SchoffhauzerSynthShared> for SchoffhauzerSynthPluginMainThread<'a> {}
@@ -180,9 +233,9 @@ impl<'a> PluginAudioPortsImpl for SchoffhauzerSynthPluginMainThread<'a> {
             writer.set(&AudioPortInfo {
                 id: ClapId::new(1),
                 name: b"main",
-                channel_count: 1,
+                channel_count: 2,
                 flags: AudioPortFlags::IS_MAIN,
-                port_type: Some(AudioPortType::MONO),
+                port_type: Some(AudioPortType::STEREO),
                 in_place_pair: None,
             })
         }
@@ -199,7 +252,10 @@ impl<'a> PluginNotePortsImpl for SchoffhauzerSynthPluginMainThread<'a> {
             writer.set(&NotePortInfo {
                 id: ClapId::new(1),
                 name: b"main",
-                supported_dialects: NoteDialects::CLAP,
+                supported_dialects: NoteDialects::CLAP
+                    | NoteDialects::MIDI
+                    | NoteDialects::MIDI_MPE
+                    | NoteDialects::MIDI2,
                 preferred_dialect: Some(NoteDialect::Clap),
             })
         }
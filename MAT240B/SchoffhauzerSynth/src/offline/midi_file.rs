@@ -0,0 +1,134 @@
+use crate::offline::script::{Script, ScriptEvent, ScriptEventKind};
+use midly::{Format, MetaMessage, MidiMessage, Smf, Timing, TrackEventKind};
+use std::error::Error;
+use std::path::Path;
+
+/// Options for turning a Standard MIDI File into a [`Script`].
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct MidiFileImport {
+    pub sample_rate: u32,
+    /// Seconds rendered after the last event, so released notes can ring out
+    pub tail: f32,
+}
+
+impl Default for MidiFileImport {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            tail: 2.0,
+        }
+    }
+}
+
+/// Microseconds per quarter note until the first tempo event
+const DEFAULT_TEMPO: u32 = 500_000;
+
+impl MidiFileImport {
+    pub fn import_file(&self, filename: impl AsRef<Path>) -> Result<Script, Box<dyn Error>> {
+        self.import(&std::fs::read(filename)?)
+    }
+
+    /// Parses a type 0 or type 1 SMF, merging all tracks into a single timeline.
+    pub fn import(&self, bytes: &[u8]) -> Result<Script, Box<dyn Error>> {
+        let smf = Smf::parse(bytes)?;
+        if smf.header.format == Format::Sequential {
+            return Err("Sequential (type 2) MIDI files are not supported".into());
+        }
+
+        let mut track_events = Vec::new();
+        for track in &smf.tracks {
+            let mut tick = 0u64;
+            for event in track {
+                tick += event.delta.as_int() as u64;
+                track_events.push((tick, event.kind));
+            }
+        }
+        // Stable, so simultaneous events keep their track order
+        track_events.sort_by_key(|(tick, _)| *tick);
+
+        let mut events = Vec::new();
+        let mut last_tick = 0u64;
+        let mut seconds = 0.0f64;
+        let mut tempo = DEFAULT_TEMPO;
+        for (tick, kind) in track_events {
+            seconds += (tick - last_tick) as f64 * self.seconds_per_tick(smf.header.timing, tempo);
+            last_tick = tick;
+            let time = (seconds * self.sample_rate as f64).round() as usize;
+
+            match kind {
+                TrackEventKind::Meta(MetaMessage::Tempo(new_tempo)) => tempo = new_tempo.as_int(),
+                TrackEventKind::Midi { channel, message } => {
+                    events.extend(
+                        Self::convert_message(channel.as_int() as u16, message)
+                            .map(|kind| ScriptEvent { time, kind }),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        let length = events.last().map_or(0, |event| event.time)
+            + (self.tail * self.sample_rate as f32) as usize;
+        Ok(Script {
+            sample_rate: self.sample_rate,
+            length,
+            events,
+        })
+    }
+
+    fn seconds_per_tick(&self, timing: Timing, tempo: u32) -> f64 {
+        match timing {
+            Timing::Metrical(ticks_per_beat) => {
+                tempo as f64 / 1_000_000.0 / ticks_per_beat.as_int() as f64
+            }
+            Timing::Timecode(fps, subframes) => 1.0 / fps.as_f32() as f64 / subframes as f64,
+        }
+    }
+
+    fn convert_message(channel: u16, message: MidiMessage) -> Option<ScriptEventKind> {
+        Some(match message {
+            MidiMessage::NoteOn { key, vel } if vel.as_int() > 0 => ScriptEventKind::NoteOn {
+                channel,
+                key: key.as_int() as u16,
+                note_id: None,
+                velocity: vel.as_int() as f64 / 127.0,
+            },
+            // Note on with zero velocity is a note off with the default release velocity
+            MidiMessage::NoteOn { key, .. } => ScriptEventKind::NoteOff {
+                channel: Some(channel),
+                key: Some(key.as_int() as u16),
+                note_id: None,
+                velocity: 64.0 / 127.0,
+            },
+            MidiMessage::NoteOff { key, vel } => ScriptEventKind::NoteOff {
+                channel: Some(channel),
+                key: Some(key.as_int() as u16),
+                note_id: None,
+                velocity: vel.as_int() as f64 / 127.0,
+            },
+            // Everything else goes through the synth's own MIDI decoding, the same as live input,
+            // so controllers, pressure and pitch bend behave the same as in a host
+            message => ScriptEventKind::Midi {
+                data: midi_bytes(channel as u8, message)?,
+            },
+        })
+    }
+}
+
+/// The MIDI 1.0 bytes of a channel voice message, padded with zeros, `None` for the ones the synth never reads.
+fn midi_bytes(channel: u8, message: MidiMessage) -> Option<[u8; 3]> {
+    Some(match message {
+        MidiMessage::NoteOff { key, vel } => [0x80 | channel, key.as_int(), vel.as_int()],
+        MidiMessage::NoteOn { key, vel } => [0x90 | channel, key.as_int(), vel.as_int()],
+        MidiMessage::Aftertouch { key, vel } => [0xa0 | channel, key.as_int(), vel.as_int()],
+        MidiMessage::Controller { controller, value } => {
+            [0xb0 | channel, controller.as_int(), value.as_int()]
+        }
+        MidiMessage::ChannelAftertouch { vel } => [0xd0 | channel, vel.as_int(), 0],
+        MidiMessage::PitchBend { bend } => {
+            let bend = bend.0.as_int();
+            [0xe0 | channel, (bend & 0x7f) as u8, (bend >> 7) as u8]
+        }
+        MidiMessage::ProgramChange { .. } => return None,
+    })
+}
@@ -0,0 +1,7 @@
+pub mod midi_file;
+pub mod renderer;
+pub mod script;
+pub mod wav;
+
+#[cfg(test)]
+mod tests;
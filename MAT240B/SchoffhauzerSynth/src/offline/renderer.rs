@@ -0,0 +1,87 @@
+use crate::offline::script::{Script, ScriptEvent, ScriptEventKind};
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::synth::poly_synth::PolySynth;
+use crate::utils::alloc_guard::forbid_alloc;
+
+/// Drives [`PolySynth`] the same way [`crate::SchoffhauzerSynthAudioProcessor`] does,
+/// but without a host: events are applied at their sample offsets and audio is rendered in blocks.
+/// Event handling and rendering run under the same allocation guard as the audio thread.
+pub struct OfflineRenderer {
+    params: SchoffhauzerSynthPluginParams,
+    synth: PolySynth,
+    sample_rate: f32,
+    block_size: usize,
+}
+
+impl OfflineRenderer {
+    pub const DEFAULT_BLOCK_SIZE: usize = 512;
+
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            params: SchoffhauzerSynthPluginParams::default(),
+            synth: PolySynth::new(sample_rate),
+            sample_rate,
+            block_size: Self::DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    pub fn with_block_size(mut self, block_size: usize) -> Self {
+        assert!(block_size > 0, "block size must be positive");
+        self.block_size = block_size;
+        self
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.synth.is_busy()
+    }
+
+    pub fn handle_event(&mut self, event: &ScriptEventKind) {
+        forbid_alloc(|| match *event {
+            ScriptEventKind::PitchBend { channel, semitones } => {
+                self.synth.set_pitch_bend(channel, semitones)
+            }
+            _ => {
+                event.with_clack_event(0, |event| self.synth.handle_event(event, &self.params));
+            }
+        })
+    }
+
+    /// Renders into `left` and `right` without applying any events, overwriting their content.
+    pub fn render_into(&mut self, left: &mut [f32], right: &mut [f32]) {
+        left.fill(0.0);
+        right.fill(0.0);
+        forbid_alloc(|| {
+            let blocks = left
+                .chunks_mut(self.block_size)
+                .zip(right.chunks_mut(self.block_size));
+            for (left, right) in blocks {
+                self.synth.synth(left, right, &self.params);
+            }
+        });
+    }
+
+    /// Renders `length` stereo samples as `[left, right]`, applying each event right before the sample at its offset.
+    /// Events past the end are ignored.
+    pub fn render(&mut self, events: &[ScriptEvent], length: usize) -> [Vec<f32>; 2] {
+        let mut events = events.to_vec();
+        events.sort_by_key(|event| event.time);
+
+        let [mut left, mut right] = [vec![0.0; length], vec![0.0; length]];
+        let mut position = 0;
+        for event in events.iter().take_while(|event| event.time < length) {
+            self.render_into(&mut left[position..event.time], &mut right[position..event.time]);
+            position = event.time;
+            self.handle_event(&event.kind);
+        }
+        self.render_into(&mut left[position..], &mut right[position..]);
+        [left, right]
+    }
+
+    pub fn render_script(script: &Script) -> [Vec<f32>; 2] {
+        Self::new(script.sample_rate as f32).render(&script.events, script.length)
+    }
+}
@@ -0,0 +1,209 @@
+use clack_plugin::events::event_types::{
+    MidiEvent, NoteChokeEvent, NoteExpressionEvent, NoteExpressionType, NoteOffEvent,
+    NoteOnEvent, ParamModEvent, ParamValueEvent,
+};
+use clack_plugin::events::{Event, Match, Pckn, UnknownEvent};
+use clack_plugin::prelude::ClapId;
+use clack_plugin::utils::Cookie;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A scripted render: everything the offline renderer needs to produce a file without a host.
+#[derive_aliases::derive(Debug, Clone, ..SerDe)]
+pub struct Script {
+    pub sample_rate: u32,
+    /// Length of the render in samples
+    pub length: usize,
+    pub events: Vec<ScriptEvent>,
+}
+
+impl Script {
+    pub fn from_reader(reader: impl io::Read) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn from_file(filename: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(io::BufReader::new(fs::File::open(filename)?))
+    }
+}
+
+#[derive_aliases::derive(..Copy, Debug, ..SerDe)]
+pub struct ScriptEvent {
+    /// Sample offset from the start of the render
+    pub time: usize,
+    #[serde(flatten)]
+    pub kind: ScriptEventKind,
+}
+
+/// Mirrors the subset of CLAP core events the synth reacts to.
+/// Omitted `channel`/`key`/`note_id` fields act as wildcards, like in CLAP.
+#[derive_aliases::derive(..Copy, Debug, ..SerDe)]
+#[serde(tag = "type")]
+pub enum ScriptEventKind {
+    NoteOn {
+        #[serde(default)]
+        channel: u16,
+        key: u16,
+        #[serde(default)]
+        note_id: Option<u32>,
+        #[serde(default = "default_velocity")]
+        velocity: f64,
+    },
+    NoteOff {
+        #[serde(default)]
+        channel: Option<u16>,
+        #[serde(default)]
+        key: Option<u16>,
+        #[serde(default)]
+        note_id: Option<u32>,
+        #[serde(default = "default_velocity")]
+        velocity: f64,
+    },
+    NoteChoke {
+        #[serde(default)]
+        channel: Option<u16>,
+        #[serde(default)]
+        key: Option<u16>,
+        #[serde(default)]
+        note_id: Option<u32>,
+    },
+    NoteExpression {
+        expression: ScriptNoteExpression,
+        #[serde(default)]
+        channel: Option<u16>,
+        #[serde(default)]
+        key: Option<u16>,
+        #[serde(default)]
+        note_id: Option<u32>,
+        value: f64,
+    },
+    ParamValue {
+        param_id: u32,
+        #[serde(default)]
+        channel: Option<u16>,
+        #[serde(default)]
+        key: Option<u16>,
+        #[serde(default)]
+        note_id: Option<u32>,
+        value: f64,
+    },
+    ParamMod {
+        param_id: u32,
+        #[serde(default)]
+        channel: Option<u16>,
+        #[serde(default)]
+        key: Option<u16>,
+        #[serde(default)]
+        note_id: Option<u32>,
+        amount: f64,
+    },
+    /// Raw MIDI 1.0 message on the note port
+    Midi {
+        data: [u8; 3],
+    },
+    /// Channel-wide pitch bend, has no CLAP core event equivalent
+    PitchBend {
+        #[serde(default)]
+        channel: u16,
+        semitones: f32,
+    },
+}
+
+/// Serializable mirror of [`NoteExpressionType`].
+#[derive_aliases::derive(..Copy, Debug, ..Eq, ..SerDe)]
+pub enum ScriptNoteExpression {
+    Volume,
+    Pan,
+    Tuning,
+    Vibrato,
+    Expression,
+    Brightness,
+    Pressure,
+}
+
+impl From<ScriptNoteExpression> for NoteExpressionType {
+    fn from(value: ScriptNoteExpression) -> Self {
+        match value {
+            ScriptNoteExpression::Volume => NoteExpressionType::Volume,
+            ScriptNoteExpression::Pan => NoteExpressionType::Pan,
+            ScriptNoteExpression::Tuning => NoteExpressionType::Tuning,
+            ScriptNoteExpression::Vibrato => NoteExpressionType::Vibrato,
+            ScriptNoteExpression::Expression => NoteExpressionType::Expression,
+            ScriptNoteExpression::Brightness => NoteExpressionType::Brightness,
+            ScriptNoteExpression::Pressure => NoteExpressionType::Pressure,
+        }
+    }
+}
+
+fn default_velocity() -> f64 {
+    1.0
+}
+
+fn to_match<T>(value: Option<T>) -> Match<T> {
+    value.map_or(Match::All, Match::Specific)
+}
+
+fn pckn(channel: Option<u16>, key: Option<u16>, note_id: Option<u32>) -> Pckn {
+    Pckn::new(0u16, to_match(channel), to_match(key), to_match(note_id))
+}
+
+/// Parameter events aren't tied to the note port, so a fully wildcarded one reaches the global value.
+fn param_pckn(channel: Option<u16>, key: Option<u16>, note_id: Option<u32>) -> Pckn {
+    Pckn::new(Match::All, to_match(channel), to_match(key), to_match(note_id))
+}
+
+impl ScriptEventKind {
+    /// Builds the equivalent CLAP event and hands it to `f`, returns `None` if there is no such event.
+    pub fn with_clack_event<R>(&self, time: u32, f: impl FnOnce(&UnknownEvent) -> R) -> Option<R> {
+        Some(match *self {
+            ScriptEventKind::NoteOn { channel, key, note_id, velocity } => f(NoteOnEvent::new(
+                time,
+                pckn(Some(channel), Some(key), note_id),
+                velocity,
+            )
+            .as_unknown()),
+            ScriptEventKind::NoteOff { channel, key, note_id, velocity } => f(NoteOffEvent::new(
+                time,
+                pckn(channel, key, note_id),
+                velocity,
+            )
+            .as_unknown()),
+            ScriptEventKind::NoteChoke { channel, key, note_id } => {
+                f(NoteChokeEvent::new(time, pckn(channel, key, note_id)).as_unknown())
+            }
+            ScriptEventKind::NoteExpression { expression, channel, key, note_id, value } => {
+                f(NoteExpressionEvent::new(
+                    time,
+                    pckn(channel, key, note_id),
+                    expression.into(),
+                    value,
+                )
+                .as_unknown())
+            }
+            ScriptEventKind::ParamValue { param_id, channel, key, note_id, value } => {
+                f(ParamValueEvent::new(
+                    time,
+                    ClapId::new(param_id),
+                    param_pckn(channel, key, note_id),
+                    value,
+                    Cookie::empty(),
+                )
+                .as_unknown())
+            }
+            ScriptEventKind::ParamMod { param_id, channel, key, note_id, amount } => {
+                f(ParamModEvent::new(
+                    time,
+                    ClapId::new(param_id),
+                    param_pckn(channel, key, note_id),
+                    amount,
+                    Cookie::empty(),
+                )
+                .as_unknown())
+            }
+            ScriptEventKind::Midi { data } => f(MidiEvent::new(time, 0, data).as_unknown()),
+            ScriptEventKind::PitchBend { .. } => return None,
+        })
+    }
+}
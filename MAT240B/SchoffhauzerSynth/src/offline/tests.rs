@@ -0,0 +1,186 @@
+//! Tests of the Standard MIDI File import.
+
+use crate::offline::midi_file::MidiFileImport;
+use crate::offline::renderer::OfflineRenderer;
+use crate::offline::script::{ScriptEvent, ScriptEventKind};
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::synth::midi::{CC_BRIGHTNESS, CC_MOD_WHEEL};
+use crate::synth::mod_matrix::ModSource;
+use crate::utils::param_enum::ParamEnum;
+
+const SAMPLE_RATE: u32 = 48000;
+const TEMPO_EVENT: u8 = 0x51;
+
+/// Builds an SMF from its header fields and tracks of `(delta, event bytes)`, adding the end of track events.
+fn smf(format: u16, division: [u8; 2], tracks: &[&[(u32, &[u8])]]) -> Vec<u8> {
+    let mut bytes = b"MThd".to_vec();
+    bytes.extend(6u32.to_be_bytes());
+    bytes.extend(format.to_be_bytes());
+    bytes.extend((tracks.len() as u16).to_be_bytes());
+    bytes.extend(division);
+    for track in tracks {
+        let mut data = Vec::new();
+        for &(delta, event) in track.iter().chain(&[(0, &[0xff, 0x2f, 0x00][..])]) {
+            data.extend(variable_length(delta));
+            data.extend(event);
+        }
+        bytes.extend(b"MTrk");
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+    }
+    bytes
+}
+
+fn variable_length(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![(value & 0x7f) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+fn tempo(micros_per_beat: u32) -> [u8; 6] {
+    let [_, a, b, c] = micros_per_beat.to_be_bytes();
+    [0xff, TEMPO_EVENT, 0x03, a, b, c]
+}
+
+fn import(bytes: &[u8]) -> Vec<ScriptEvent> {
+    let import = MidiFileImport { sample_rate: SAMPLE_RATE, ..MidiFileImport::default() };
+    import.import(bytes).unwrap().events
+}
+
+fn times(events: &[ScriptEvent]) -> Vec<usize> {
+    events.iter().map(|event| event.time).collect()
+}
+
+#[test]
+fn metrical_timing_defaults_to_120_bpm() {
+    // 96 ticks per quarter note, a quarter note lasts half a second until a tempo event
+    let bytes = smf(0, [0, 96], &[&[(0, &[0x90, 60, 100]), (96, &[0x80, 60, 0]), (48, &[0x90, 62, 100])]]);
+    assert_eq!(times(&import(&bytes)), [0, 24000, 36000]);
+}
+
+#[test]
+fn tempo_changes_apply_from_their_tick() {
+    let bytes = smf(
+        0,
+        [0, 96],
+        &[&[(0, &[0x90, 60, 100]), (96, &tempo(250_000)), (0, &[0x80, 60, 0]), (96, &[0x90, 62, 100])]],
+    );
+    // Half a second at 120 BPM, then a quarter of a second at 240 BPM
+    assert_eq!(times(&import(&bytes)), [0, 24000, 36000]);
+}
+
+#[test]
+fn tracks_share_the_tempo_map() {
+    // Type 1, the tempo lives in the first track and the notes in the second
+    let bytes = smf(
+        1,
+        [0, 96],
+        &[&[(48, &tempo(1_000_000))], &[(96, &[0x90, 60, 100]), (96, &[0x80, 60, 0])]],
+    );
+    // 48 ticks at 120 BPM, 48 at 60 BPM, then another 96 at 60 BPM
+    assert_eq!(times(&import(&bytes)), [12000 + 24000, 12000 + 24000 + 48000]);
+}
+
+#[test]
+fn timecode_timing_ignores_tempo() {
+    // 25 frames per second with 40 ticks each is one tick per millisecond
+    let bytes = smf(0, [-25i8 as u8, 40], &[&[(0, &tempo(250_000)), (500, &[0x90, 60, 100]), (250, &[0x80, 60, 0])]]);
+    assert_eq!(times(&import(&bytes)), [24000, 36000]);
+}
+
+#[test]
+fn note_on_without_velocity_is_a_note_off() {
+    let bytes = smf(0, [0, 96], &[&[(0, &[0x91, 60, 127]), (96, &[0x91, 60, 0])]]);
+    let events = import(&bytes);
+    assert!(matches!(
+        events[0].kind,
+        ScriptEventKind::NoteOn { channel: 1, key: 60, velocity, .. } if velocity == 1.0
+    ));
+    assert!(matches!(
+        events[1].kind,
+        ScriptEventKind::NoteOff { channel: Some(1), key: Some(60), .. }
+    ));
+}
+
+#[test]
+fn running_status_repeats_the_last_status() {
+    // The second and third events omit the note on status byte
+    let bytes = smf(0, [0, 96], &[&[(0, &[0x91, 60, 100]), (96, &[62, 100]), (96, &[60, 0])]]);
+    let events = import(&bytes);
+    assert_eq!(times(&events), [0, 24000, 48000]);
+    assert!(matches!(events[1].kind, ScriptEventKind::NoteOn { channel: 1, key: 62, .. }));
+    assert!(matches!(
+        events[2].kind,
+        ScriptEventKind::NoteOff { channel: Some(1), key: Some(60), .. }
+    ));
+}
+
+#[test]
+fn length_includes_the_tail() {
+    let import = MidiFileImport { sample_rate: SAMPLE_RATE, tail: 1.5 };
+    let bytes = smf(0, [0, 96], &[&[(0, &[0x90, 60, 100]), (192, &[0x80, 60, 0])]]);
+    assert_eq!(import.import(&bytes).unwrap().length, 48000 + 72000);
+}
+
+#[test]
+fn sequential_files_are_rejected() {
+    let bytes = smf(2, [0, 96], &[&[(0, &[0x90, 60, 100])]]);
+    assert!(MidiFileImport::default().import(&bytes).is_err());
+}
+
+#[test]
+fn channel_voice_messages_are_passed_through_as_midi() {
+    let bytes = smf(
+        0,
+        [0, 96],
+        &[&[(0, &[0xb2, CC_MOD_WHEEL, 100]), (0, &[0xb2, CC_BRIGHTNESS, 20]), (0, &[0xe2, 0x01, 0x60]), (0, &[0xd2, 90])]],
+    );
+    let data = import(&bytes)
+        .iter()
+        .map(|event| match event.kind {
+            ScriptEventKind::Midi { data } => data,
+            ref kind => panic!("{kind:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(data, [[0xb2, CC_MOD_WHEEL, 100], [0xb2, CC_BRIGHTNESS, 20], [0xe2, 0x01, 0x60], [0xd2, 90, 0]]);
+}
+
+/// Renders a held note with `controller` set to `value` on its channel right after the note on.
+fn render_with_controller(events: &[ScriptEvent], controller: u8, value: u8) -> Vec<f32> {
+    let bytes = smf(0, [0, 96], &[&[(0, &[0x90, 48, 100]), (0, &[0xb0, controller, value])]]);
+    let mut events = events.to_vec();
+    events.extend(import(&bytes));
+    let [left, _] = OfflineRenderer::new(SAMPLE_RATE as f32).render(&events, SAMPLE_RATE as usize / 4);
+    left
+}
+
+fn differs(a: &[f32], b: &[f32]) -> bool {
+    a.iter().zip(b).any(|(a, b)| (a - b).abs() > 1e-3)
+}
+
+#[test]
+fn mod_wheel_reaches_the_matrix() {
+    let slot = &SchoffhauzerSynthPluginParams::MOD_MATRIX[0];
+    // Destination 1 is the volume
+    let route = [(slot.source, ModSource::ModWheel.value() as f64), (slot.destination, 1.0), (slot.amount, -1.0)]
+        .map(|(info, value)| ScriptEvent {
+            time: 0,
+            kind: ScriptEventKind::ParamValue { param_id: info.id.get(), channel: None, key: None, note_id: None, value },
+        });
+    assert!(differs(
+        &render_with_controller(&route, CC_MOD_WHEEL, 0),
+        &render_with_controller(&route, CC_MOD_WHEEL, 127),
+    ));
+}
+
+#[test]
+fn brightness_reaches_the_voices() {
+    assert!(differs(
+        &render_with_controller(&[], CC_BRIGHTNESS, 0),
+        &render_with_controller(&[], CC_BRIGHTNESS, 127),
+    ));
+}
@@ -0,0 +1,59 @@
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::error::Error;
+use std::path::Path;
+use std::{fs, io};
+
+/// Writes planar channels as an interleaved 32-bit float WAV stream.
+pub fn write_wav<W: io::Write + io::Seek>(
+    writer: W,
+    sample_rate: u32,
+    channels: &[&[f32]],
+) -> Result<(), Box<dyn Error>> {
+    let spec = WavSpec {
+        channels: channels.len() as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let length = channels.iter().map(|channel| channel.len()).max().unwrap_or(0);
+    let mut writer = WavWriter::new(writer, spec)?;
+    for i in 0..length {
+        for channel in channels {
+            writer.write_sample(channel.get(i).copied().unwrap_or(0.0))?;
+        }
+    }
+    Ok(writer.finalize()?)
+}
+
+pub fn write_wav_file(
+    filename: impl AsRef<Path>,
+    sample_rate: u32,
+    channels: &[&[f32]],
+) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = filename.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_wav(
+        io::BufWriter::new(fs::File::create(filename)?),
+        sample_rate,
+        channels,
+    )
+}
+
+/// Decoded audio, as read back by [`read_wav_file`].
+pub struct Wav {
+    pub sample_rate: u32,
+    /// Planar, one `Vec` per channel
+    pub channels: Vec<Vec<f32>>,
+}
+
+/// Reads a 32-bit float WAV file back into planar channels.
+pub fn read_wav_file(filename: impl AsRef<Path>) -> Result<Wav, Box<dyn Error>> {
+    let mut reader = WavReader::open(filename)?;
+    let spec = reader.spec();
+    let mut channels = vec![Vec::new(); spec.channels as usize];
+    for (i, sample) in reader.samples::<f32>().enumerate() {
+        channels[i % spec.channels as usize].push(sample?);
+    }
+    Ok(Wav { sample_rate: spec.sample_rate, channels })
+}
@@ -1,6 +1,19 @@
+use crate::synth::filter::{FilterMode, FilterSlope};
+use crate::synth::lfo::{Lfo, LfoRetrigger, LfoShape, LfoSyncRate};
+use crate::synth::mod_matrix::{MOD_MATRIX_SLOTS, ModSlot, ModSource, destination_from_value};
+use crate::synth::modulation::ModDestination;
+use crate::synth::note_expression::PressureDestination;
+use crate::synth::note_stack::NotePriority;
+use crate::synth::oscillator::{OSCILLATOR_COUNT, Oscillator, OscillatorLink};
+use crate::synth::poly_synth::{PlayMode, VoiceStealing};
+use crate::synth::synth::Waveform;
+use crate::utils::atomic_param::AtomicParam;
 use crate::utils::db::DB;
 use crate::utils::envelope::ADSR;
+use crate::utils::midi_note::MidiNote;
 use crate::utils::modulated::Modulated;
+use crate::utils::param_enum::ParamEnum;
+use crate::utils::velocity::VelocityCurve;
 use crate::{SchoffhauzerSynthAudioProcessor, SchoffhauzerSynthPluginMainThread};
 use clack_extensions::params::{
     ParamDisplayWriter, ParamInfo, ParamInfoFlags, ParamInfoWriter, PluginAudioProcessorParams,
@@ -15,9 +28,8 @@ use repetitive::repetitive;
 use static_assertions::assert_impl_all;
 use std::f64;
 use std::ffi::CStr;
-use std::fmt::Write as _;
+use std::fmt::Write;
 use std::str::FromStr;
-use std::sync::RwLock;
 
 pub trait ParamInfoFlagsExt {
     const IS_AUTOMATABLE_ALL: ParamInfoFlags = ParamInfoFlags::from_bits_truncate(
@@ -64,30 +76,135 @@ impl ParamInfoFlagsExt for ParamInfoFlags {}
 // }
 
 pub struct SchoffhauzerSynthPluginParams {
-    pub volume: RwLock<Modulated<DB<f32>>>,
-    pub adsr: RwLock<ADSR<Modulated<f32>>>,
-    pub hf_rolloff: RwLock<Modulated<f32>>,
+    pub volume: AtomicParam<Modulated<DB<f32>>>,
+    pub adsr: AtomicParam<ADSR<Modulated<f32>>>,
+    pub oscillators: AtomicParam<[Oscillator<Modulated<f32>>; OSCILLATOR_COUNT]>,
+    /// OSC 2 and OSC 3 relative to OSC 1
+    pub oscillator_links: AtomicParam<[OscillatorLink<Modulated<f32>>; OSCILLATOR_COUNT - 1]>,
+    pub velocity_amount: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`VelocityCurve::from_value`]
+    pub velocity_curve: AtomicParam<Modulated<f32>>,
+    pub velocity_hf_rolloff: AtomicParam<Modulated<f32>>,
+    /// How much faster a harder release lets go, through the velocity curve
+    pub velocity_release: AtomicParam<Modulated<f32>>,
+    pub max_polyphony: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`VoiceStealing::from_value`]
+    pub voice_stealing: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`PlayMode::from_value`]
+    pub play_mode: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`NotePriority::from_value`]
+    pub note_priority: AtomicParam<Modulated<f32>>,
+    pub portamento: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`PressureDestination::from_value`]
+    pub pressure_destination: AtomicParam<Modulated<f32>>,
+    pub pressure_amount: AtomicParam<Modulated<f32>>,
+    /// In semitones, applied to MIDI pitch bend messages
+    pub pitch_bend_range: AtomicParam<Modulated<f32>>,
+    pub pan: AtomicParam<Modulated<f32>>,
+    pub width: AtomicParam<Modulated<f32>>,
+    pub unison_voices: AtomicParam<Modulated<f32>>,
+    /// In cents, from the center to the outermost copy
+    pub unison_detune: AtomicParam<Modulated<f32>>,
+    pub unison_spread: AtomicParam<Modulated<f32>>,
+    pub sub_level: AtomicParam<Modulated<f32>>,
+    /// Stepped, in octaves below OSC 1
+    pub sub_octave: AtomicParam<Modulated<f32>>,
+    /// Fraction of the period a [`Waveform::Pulse`] is high
+    pub pulse_width: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`FilterMode::from_value`]
+    pub filter_mode: AtomicParam<Modulated<f32>>,
+    /// Stepped, see [`FilterSlope::from_value`]
+    pub filter_slope: AtomicParam<Modulated<f32>>,
+    /// As a MIDI note number, so modulation is in semitones
+    pub filter_cutoff: AtomicParam<Modulated<f32>>,
+    pub filter_resonance: AtomicParam<Modulated<f32>>,
+    /// At `1.0` the cutoff moves one semitone per semitone from middle C
+    pub filter_key_tracking: AtomicParam<Modulated<f32>>,
+    pub mod_env: AtomicParam<ADSR<Modulated<f32>>>,
+    /// Stepped, see [`ModDestination::from_value`]
+    pub mod_env_destination: AtomicParam<Modulated<f32>>,
+    pub mod_env_amount: AtomicParam<Modulated<f32>>,
+    /// Runs separately in each voice
+    pub voice_lfo: AtomicParam<Lfo<Modulated<f32>>>,
+    /// Shared by all voices
+    pub global_lfo: AtomicParam<Lfo<Modulated<f32>>>,
+    pub mod_matrix: AtomicParam<[ModSlot<Modulated<f32>>; MOD_MATRIX_SLOTS]>,
 }
 
 type Params = SchoffhauzerSynthPluginParams;
 
+const LFOS: [Lfo<&ParamInfo<'static>>; 2] = [Params::VOICE_LFO, Params::GLOBAL_LFO];
+
+/// `"Module Name"`, or just the name for parameters outside of any module.
+fn display_name(info: &ParamInfo<'_>) -> String {
+    let name = String::from_utf8_lossy(info.name);
+    match info.module {
+        b"" => name.into_owned(),
+        module => format!("{} {name}", String::from_utf8_lossy(module)),
+    }
+}
+
 assert_impl_all!(SchoffhauzerSynthPluginParams: Send, Sync);
 
 impl Default for SchoffhauzerSynthPluginParams {
     fn default() -> Self {
         Self {
-            volume: RwLock::new(Modulated::new(
+            volume: AtomicParam::new(Modulated::new(
                 DB(Params::VOLUME.default_value as f32),
                 DB(0.0),
             )),
             adsr: repetitive! {
-                RwLock::new(ADSR {
+                AtomicParam::new(ADSR {
                     @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
                         @field: Modulated::new(Params::ADSR.@field.default_value as f32, 0.0),
                     }
                 })
             },
-            hf_rolloff: RwLock::new(Modulated::new(Params::HF_ROLLOFF.default_value as f32, 0.0)),
+            oscillators: AtomicParam::new(
+                Params::OSCILLATORS.map(|osc| osc.map(|info| Modulated::new(info.default_value as f32, 0.0))),
+            ),
+            oscillator_links: AtomicParam::new(
+                Params::OSCILLATOR_LINKS.map(|link| link.map(|info| Modulated::new(info.default_value as f32, 0.0))),
+            ),
+            velocity_amount: AtomicParam::new(Modulated::new(Params::VELOCITY_AMOUNT.default_value as f32, 0.0)),
+            velocity_curve: AtomicParam::new(Modulated::new(Params::VELOCITY_CURVE.default_value as f32, 0.0)),
+            velocity_hf_rolloff: AtomicParam::new(Modulated::new(Params::VELOCITY_HF_ROLLOFF.default_value as f32, 0.0)),
+            velocity_release: AtomicParam::new(Modulated::new(Params::VELOCITY_RELEASE.default_value as f32, 0.0)),
+            max_polyphony: AtomicParam::new(Modulated::new(Params::MAX_POLYPHONY.default_value as f32, 0.0)),
+            voice_stealing: AtomicParam::new(Modulated::new(Params::VOICE_STEALING.default_value as f32, 0.0)),
+            play_mode: AtomicParam::new(Modulated::new(Params::PLAY_MODE.default_value as f32, 0.0)),
+            note_priority: AtomicParam::new(Modulated::new(Params::NOTE_PRIORITY.default_value as f32, 0.0)),
+            portamento: AtomicParam::new(Modulated::new(Params::PORTAMENTO.default_value as f32, 0.0)),
+            pressure_destination: AtomicParam::new(Modulated::new(Params::PRESSURE_DESTINATION.default_value as f32, 0.0)),
+            pressure_amount: AtomicParam::new(Modulated::new(Params::PRESSURE_AMOUNT.default_value as f32, 0.0)),
+            pitch_bend_range: AtomicParam::new(Modulated::new(Params::PITCH_BEND_RANGE.default_value as f32, 0.0)),
+            pan: AtomicParam::new(Modulated::new(Params::PAN.default_value as f32, 0.0)),
+            width: AtomicParam::new(Modulated::new(Params::WIDTH.default_value as f32, 0.0)),
+            unison_voices: AtomicParam::new(Modulated::new(Params::UNISON_VOICES.default_value as f32, 0.0)),
+            unison_detune: AtomicParam::new(Modulated::new(Params::UNISON_DETUNE.default_value as f32, 0.0)),
+            unison_spread: AtomicParam::new(Modulated::new(Params::UNISON_SPREAD.default_value as f32, 0.0)),
+            sub_level: AtomicParam::new(Modulated::new(Params::SUB_LEVEL.default_value as f32, 0.0)),
+            sub_octave: AtomicParam::new(Modulated::new(Params::SUB_OCTAVE.default_value as f32, 0.0)),
+            pulse_width: AtomicParam::new(Modulated::new(Params::PULSE_WIDTH.default_value as f32, 0.0)),
+            filter_mode: AtomicParam::new(Modulated::new(Params::FILTER_MODE.default_value as f32, 0.0)),
+            filter_slope: AtomicParam::new(Modulated::new(Params::FILTER_SLOPE.default_value as f32, 0.0)),
+            filter_cutoff: AtomicParam::new(Modulated::new(Params::FILTER_CUTOFF.default_value as f32, 0.0)),
+            filter_resonance: AtomicParam::new(Modulated::new(Params::FILTER_RESONANCE.default_value as f32, 0.0)),
+            filter_key_tracking: AtomicParam::new(Modulated::new(Params::FILTER_KEY_TRACKING.default_value as f32, 0.0)),
+            mod_env: repetitive! {
+                AtomicParam::new(ADSR {
+                    @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                        @field: Modulated::new(Params::MOD_ENV.@field.default_value as f32, 0.0),
+                    }
+                })
+            },
+            mod_env_destination: AtomicParam::new(Modulated::new(Params::MOD_ENV_DESTINATION.default_value as f32, 0.0)),
+            mod_env_amount: AtomicParam::new(Modulated::new(Params::MOD_ENV_AMOUNT.default_value as f32, 0.0)),
+            voice_lfo: AtomicParam::new(Params::VOICE_LFO.map(|info| Modulated::new(info.default_value as f32, 0.0))),
+            global_lfo: AtomicParam::new(Params::GLOBAL_LFO.map(|info| Modulated::new(info.default_value as f32, 0.0))),
+            mod_matrix: AtomicParam::new(
+                Params::MOD_MATRIX.map(|slot| slot.map(|info| Modulated::new(info.default_value as f32, 0.0))),
+            ),
         }
     }
 }
@@ -99,7 +216,7 @@ macro_rules! join_str {
 }
 
 macro_rules! param_info {
-    (id $id:literal, $($($module:literal)/+)?@$name:literal, $default:literal in $min:literal..=$max:literal $(, $($flags:ident)|+)?) => {
+    (id $id:literal, $($($module:literal)/+)?@$name:literal, $default:literal in $min:literal..=$max:expr $(, $($flags:ident)|+)?) => {
         ParamInfo {
             id: ClapId::new($id),
             flags: ParamInfoFlags::from_bits_truncate(0 $($(| ParamInfoFlags::$flags.bits())+)?),
@@ -125,18 +242,344 @@ impl SchoffhauzerSynthPluginParams {
         release_duration: &param_info!(id 6, "ADSR"@"Release Duration", 0.3 in 0.0..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
         release_power: &param_info!(id 7, "ADSR"@"Release Power", 0.7 in 0.2..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
     };
-    pub const HF_ROLLOFF: &ParamInfo<'static> = &param_info!(id 8, "OSC"@"High Frequency Rolloff", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const HF_ROLLOFF: &ParamInfo<'static> = &param_info!(id 8, "OSC 1"@"High Frequency Rolloff", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const VELOCITY_AMOUNT: &ParamInfo<'static> = &param_info!(id 9, "Velocity"@"Amount", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const VELOCITY_CURVE: &ParamInfo<'static> = &param_info!(id 10, "Velocity"@"Curve", 0.0 in 0.0..=VelocityCurve::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const VELOCITY_HF_ROLLOFF: &ParamInfo<'static> = &param_info!(id 11, "Velocity"@"High Frequency Rolloff", 0.0 in -1.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const VELOCITY_RELEASE: &ParamInfo<'static> = &param_info!(id 111, "Velocity"@"Release", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const MAX_POLYPHONY: &ParamInfo<'static> = &param_info!(id 12, "Voices"@"Max Polyphony", 32.0 in 1.0..=64.0, IS_AUTOMATABLE | IS_STEPPED);
+    pub const VOICE_STEALING: &ParamInfo<'static> = &param_info!(id 13, "Voices"@"Stealing", 0.0 in 0.0..=VoiceStealing::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const PLAY_MODE: &ParamInfo<'static> = &param_info!(id 14, "Voices"@"Play Mode", 0.0 in 0.0..=PlayMode::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const NOTE_PRIORITY: &ParamInfo<'static> = &param_info!(id 15, "Voices"@"Note Priority", 0.0 in 0.0..=NotePriority::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const PORTAMENTO: &ParamInfo<'static> = &param_info!(id 16, "Voices"@"Portamento", 0.0 in 0.0..=2.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const PRESSURE_DESTINATION: &ParamInfo<'static> = &param_info!(id 17, "Pressure"@"Destination", 0.0 in 0.0..=PressureDestination::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const PRESSURE_AMOUNT: &ParamInfo<'static> = &param_info!(id 18, "Pressure"@"Amount", 1.0 in -1.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const PITCH_BEND_RANGE: &ParamInfo<'static> = &param_info!(id 19, "MIDI"@"Pitch Bend Range", 2.0 in 0.0..=48.0, IS_AUTOMATABLE | IS_STEPPED);
+    pub const PAN: &ParamInfo<'static> = &param_info!(id 20, "Stereo"@"Pan", 0.0 in -1.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const WIDTH: &ParamInfo<'static> = &param_info!(id 21, "Stereo"@"Width", 1.0 in 0.0..=2.0, IS_AUTOMATABLE | IS_MODULATABLE);
+    pub const UNISON_VOICES: &ParamInfo<'static> = &param_info!(id 22, "OSC"@"Unison Voices", 1.0 in 1.0..=8.0, IS_AUTOMATABLE | IS_STEPPED);
+    pub const UNISON_DETUNE: &ParamInfo<'static> = &param_info!(id 23, "OSC"@"Unison Detune", 15.0 in 0.0..=100.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const UNISON_SPREAD: &ParamInfo<'static> = &param_info!(id 24, "OSC"@"Unison Spread", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    /// Only OSC 1 is audible by default, its `hf_rolloff` is [`Self::HF_ROLLOFF`]
+    pub const OSCILLATORS: [Oscillator<&ParamInfo<'static>>; OSCILLATOR_COUNT] = [
+        Oscillator {
+            waveform: &param_info!(id 41, "OSC 1"@"Waveform", 0.0 in 0.0..=Waveform::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            octave: &param_info!(id 25, "OSC 1"@"Octave", 0.0 in -3.0..=3.0, IS_AUTOMATABLE | IS_STEPPED),
+            semitone: &param_info!(id 26, "OSC 1"@"Semitone", 0.0 in -12.0..=12.0, IS_AUTOMATABLE | IS_STEPPED),
+            fine: &param_info!(id 27, "OSC 1"@"Fine", 0.0 in -100.0..=100.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            level: &param_info!(id 28, "OSC 1"@"Level", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            hf_rolloff: Self::HF_ROLLOFF,
+        },
+        Oscillator {
+            waveform: &param_info!(id 42, "OSC 2"@"Waveform", 0.0 in 0.0..=Waveform::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            octave: &param_info!(id 29, "OSC 2"@"Octave", 0.0 in -3.0..=3.0, IS_AUTOMATABLE | IS_STEPPED),
+            semitone: &param_info!(id 30, "OSC 2"@"Semitone", 0.0 in -12.0..=12.0, IS_AUTOMATABLE | IS_STEPPED),
+            fine: &param_info!(id 31, "OSC 2"@"Fine", 0.0 in -100.0..=100.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            level: &param_info!(id 32, "OSC 2"@"Level", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            hf_rolloff: &param_info!(id 33, "OSC 2"@"High Frequency Rolloff", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        },
+        Oscillator {
+            waveform: &param_info!(id 43, "OSC 3"@"Waveform", 0.0 in 0.0..=Waveform::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            octave: &param_info!(id 34, "OSC 3"@"Octave", 0.0 in -3.0..=3.0, IS_AUTOMATABLE | IS_STEPPED),
+            semitone: &param_info!(id 35, "OSC 3"@"Semitone", 0.0 in -12.0..=12.0, IS_AUTOMATABLE | IS_STEPPED),
+            fine: &param_info!(id 36, "OSC 3"@"Fine", 0.0 in -100.0..=100.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            level: &param_info!(id 37, "OSC 3"@"Level", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+            hf_rolloff: &param_info!(id 38, "OSC 3"@"High Frequency Rolloff", 1.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        },
+    ];
+    pub const OSCILLATOR_LINKS: [OscillatorLink<&ParamInfo<'static>>; OSCILLATOR_COUNT - 1] = [
+        OscillatorLink {
+            sync: &param_info!(id 45, "OSC 2"@"Sync", 0.0 in 0.0..=1.0, IS_AUTOMATABLE | IS_STEPPED),
+            ring: &param_info!(id 46, "OSC 2"@"Ring Mod", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        },
+        OscillatorLink {
+            sync: &param_info!(id 47, "OSC 3"@"Sync", 0.0 in 0.0..=1.0, IS_AUTOMATABLE | IS_STEPPED),
+            ring: &param_info!(id 48, "OSC 3"@"Ring Mod", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        },
+    ];
+    pub const SUB_LEVEL: &ParamInfo<'static> = &param_info!(id 39, "Sub"@"Level", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const SUB_OCTAVE: &ParamInfo<'static> = &param_info!(id 40, "Sub"@"Octave", -1.0 in -2.0..=-1.0, IS_AUTOMATABLE | IS_STEPPED);
+    pub const PULSE_WIDTH: &ParamInfo<'static> = &param_info!(id 44, "OSC"@"Pulse Width", 0.5 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const FILTER_MODE: &ParamInfo<'static> = &param_info!(id 49, "Filter"@"Mode", 0.0 in 0.0..=FilterMode::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const FILTER_SLOPE: &ParamInfo<'static> = &param_info!(id 50, "Filter"@"Slope", 0.0 in 0.0..=FilterSlope::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const FILTER_CUTOFF: &ParamInfo<'static> = &param_info!(id 51, "Filter"@"Cutoff", 135.0 in 16.0..=135.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const FILTER_RESONANCE: &ParamInfo<'static> = &param_info!(id 52, "Filter"@"Resonance", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    pub const FILTER_KEY_TRACKING: &ParamInfo<'static> = &param_info!(id 53, "Filter"@"Key Tracking", 0.0 in 0.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE);
+    pub const MOD_ENV: ADSR<&ParamInfo<'static>> = ADSR {
+        attack_duration: &param_info!(id 54, "Mod Env"@"Attack Duration", 0.0 in 0.0..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        attack_power: &param_info!(id 55, "Mod Env"@"Attack Power", 1.0 in 0.2..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        decay_duration: &param_info!(id 56, "Mod Env"@"Decay Duration", 0.5 in 0.0..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        decay_power: &param_info!(id 57, "Mod Env"@"Decay Power", 1.0 in 0.2..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        sustain: &param_info!(id 58, "Mod Env"@"Sustain", 0.0 in 0.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        release_duration: &param_info!(id 59, "Mod Env"@"Release Duration", 0.3 in 0.0..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        release_power: &param_info!(id 60, "Mod Env"@"Release Power", 1.0 in 0.2..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+    };
+    pub const MOD_ENV_DESTINATION: &ParamInfo<'static> = &param_info!(id 61, "Mod Env"@"Destination", 0.0 in 0.0..=ModDestination::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM);
+    pub const MOD_ENV_AMOUNT: &ParamInfo<'static> = &param_info!(id 62, "Mod Env"@"Amount", 0.5 in -1.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL);
+    /// Restarts with every note by default
+    pub const VOICE_LFO: Lfo<&ParamInfo<'static>> = Lfo {
+        shape: &param_info!(id 63, "Voice LFO"@"Shape", 0.0 in 0.0..=LfoShape::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        rate: &param_info!(id 64, "Voice LFO"@"Rate", 2.0 in 0.01..=20.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        tempo_sync: &param_info!(id 65, "Voice LFO"@"Tempo Sync", 0.0 in 0.0..=1.0, IS_AUTOMATABLE | IS_STEPPED),
+        sync_rate: &param_info!(id 66, "Voice LFO"@"Sync Rate", 4.0 in 0.0..=LfoSyncRate::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        retrigger: &param_info!(id 67, "Voice LFO"@"Retrigger", 1.0 in 0.0..=LfoRetrigger::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        fade_in: &param_info!(id 68, "Voice LFO"@"Fade In", 0.0 in 0.0..=5.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+        destination: &param_info!(id 69, "Voice LFO"@"Destination", 0.0 in 0.0..=ModDestination::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        amount: &param_info!(id 70, "Voice LFO"@"Amount", 0.5 in -1.0..=1.0, IS_AUTOMATABLE_AND_MODULATABLE_ALL),
+    };
+    /// Only modulatable globally, since all voices share it.
+    /// The matrix reaches its fade-in and amount per voice, but not its rate.
+    pub const GLOBAL_LFO: Lfo<&ParamInfo<'static>> = Lfo {
+        shape: &param_info!(id 71, "Global LFO"@"Shape", 0.0 in 0.0..=LfoShape::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        rate: &param_info!(id 72, "Global LFO"@"Rate", 2.0 in 0.01..=20.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        tempo_sync: &param_info!(id 73, "Global LFO"@"Tempo Sync", 0.0 in 0.0..=1.0, IS_AUTOMATABLE | IS_STEPPED),
+        sync_rate: &param_info!(id 74, "Global LFO"@"Sync Rate", 4.0 in 0.0..=LfoSyncRate::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        retrigger: &param_info!(id 75, "Global LFO"@"Retrigger", 0.0 in 0.0..=LfoRetrigger::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        fade_in: &param_info!(id 76, "Global LFO"@"Fade In", 0.0 in 0.0..=5.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        destination: &param_info!(id 77, "Global LFO"@"Destination", 0.0 in 0.0..=ModDestination::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+        amount: &param_info!(id 78, "Global LFO"@"Amount", 0.5 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+    };
+    pub const MOD_MATRIX: [ModSlot<&ParamInfo<'static>>; MOD_MATRIX_SLOTS] = [
+        ModSlot {
+            source: &param_info!(id 79, "Matrix 1"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 80, "Matrix 1"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 81, "Matrix 1"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 82, "Matrix 1"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 83, "Matrix 2"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 84, "Matrix 2"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 85, "Matrix 2"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 86, "Matrix 2"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 87, "Matrix 3"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 88, "Matrix 3"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 89, "Matrix 3"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 90, "Matrix 3"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 91, "Matrix 4"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 92, "Matrix 4"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 93, "Matrix 4"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 94, "Matrix 4"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 95, "Matrix 5"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 96, "Matrix 5"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 97, "Matrix 5"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 98, "Matrix 5"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 99, "Matrix 6"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 100, "Matrix 6"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 101, "Matrix 6"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 102, "Matrix 6"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 103, "Matrix 7"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 104, "Matrix 7"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 105, "Matrix 7"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 106, "Matrix 7"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+        ModSlot {
+            source: &param_info!(id 107, "Matrix 8"@"Source", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            via: &param_info!(id 108, "Matrix 8"@"Via", 0.0 in 0.0..=ModSource::MAX_VALUE, IS_AUTOMATABLE | IS_STEPPED | IS_ENUM),
+            destination: &param_info!(id 109, "Matrix 8"@"Destination", 0.0 in 0.0..=46.0, IS_AUTOMATABLE | IS_STEPPED),
+            amount: &param_info!(id 110, "Matrix 8"@"Amount", 0.0 in -1.0..=1.0, IS_AUTOMATABLE | IS_MODULATABLE),
+        },
+    ];
+    /// Every continuous parameter the voices read, in parameter id order up to the first appended one.
+    /// Only append, saved states refer to destinations by index.
+    ///
+    /// The global LFO rate is the only continuous parameter left out, all voices share the LFO's phase.
+    pub const MOD_MATRIX_DESTINATIONS: [&ParamInfo<'static>; 46] = [
+        Self::VOLUME,
+        Self::ADSR.attack_duration,
+        Self::ADSR.attack_power,
+        Self::ADSR.decay_duration,
+        Self::ADSR.decay_power,
+        Self::ADSR.sustain,
+        Self::ADSR.release_duration,
+        Self::ADSR.release_power,
+        Self::HF_ROLLOFF,
+        Self::VELOCITY_AMOUNT,
+        Self::VELOCITY_HF_ROLLOFF,
+        Self::PORTAMENTO,
+        Self::PRESSURE_AMOUNT,
+        Self::PAN,
+        Self::UNISON_DETUNE,
+        Self::UNISON_SPREAD,
+        Self::OSCILLATORS[0].fine,
+        Self::OSCILLATORS[0].level,
+        Self::OSCILLATORS[1].fine,
+        Self::OSCILLATORS[1].level,
+        Self::OSCILLATORS[1].hf_rolloff,
+        Self::OSCILLATORS[2].fine,
+        Self::OSCILLATORS[2].level,
+        Self::OSCILLATORS[2].hf_rolloff,
+        Self::SUB_LEVEL,
+        Self::PULSE_WIDTH,
+        Self::OSCILLATOR_LINKS[0].ring,
+        Self::OSCILLATOR_LINKS[1].ring,
+        Self::FILTER_CUTOFF,
+        Self::FILTER_RESONANCE,
+        Self::FILTER_KEY_TRACKING,
+        Self::MOD_ENV.attack_duration,
+        Self::MOD_ENV.attack_power,
+        Self::MOD_ENV.decay_duration,
+        Self::MOD_ENV.decay_power,
+        Self::MOD_ENV.sustain,
+        Self::MOD_ENV.release_duration,
+        Self::MOD_ENV.release_power,
+        Self::MOD_ENV_AMOUNT,
+        Self::VOICE_LFO.rate,
+        Self::VOICE_LFO.fade_in,
+        Self::VOICE_LFO.amount,
+        Self::WIDTH,
+        Self::GLOBAL_LFO.fade_in,
+        Self::GLOBAL_LFO.amount,
+        Self::VELOCITY_RELEASE,
+    ];
 
     pub fn get_volume(&self) -> Modulated<DB<f32>> {
-        *self.volume.read().unwrap()
+        self.volume.load()
     }
 
     pub fn get_adsr(&self) -> ADSR<Modulated<f32>> {
-        *self.adsr.read().unwrap()
+        self.adsr.load()
+    }
+
+    pub fn get_oscillators(&self) -> [Oscillator<Modulated<f32>>; OSCILLATOR_COUNT] {
+        self.oscillators.load()
+    }
+
+    pub fn get_oscillator_links(&self) -> [OscillatorLink<Modulated<f32>>; OSCILLATOR_COUNT - 1] {
+        self.oscillator_links.load()
+    }
+
+    pub fn get_velocity_amount(&self) -> Modulated<f32> {
+        self.velocity_amount.load()
+    }
+
+    pub fn get_velocity_curve(&self) -> VelocityCurve {
+        VelocityCurve::from_value(self.velocity_curve.load().value)
+    }
+
+    pub fn get_velocity_hf_rolloff(&self) -> Modulated<f32> {
+        self.velocity_hf_rolloff.load()
+    }
+
+    pub fn get_velocity_release(&self) -> Modulated<f32> {
+        self.velocity_release.load()
+    }
+
+    pub fn get_max_polyphony(&self) -> usize {
+        self.max_polyphony.load().value.round().max(1.0) as usize
+    }
+
+    pub fn get_voice_stealing(&self) -> VoiceStealing {
+        VoiceStealing::from_value(self.voice_stealing.load().value)
+    }
+
+    pub fn get_play_mode(&self) -> PlayMode {
+        PlayMode::from_value(self.play_mode.load().value)
+    }
+
+    pub fn get_note_priority(&self) -> NotePriority {
+        NotePriority::from_value(self.note_priority.load().value)
+    }
+
+    pub fn get_portamento(&self) -> Modulated<f32> {
+        self.portamento.load()
+    }
+
+    pub fn get_pressure_destination(&self) -> PressureDestination {
+        PressureDestination::from_value(self.pressure_destination.load().value)
+    }
+
+    pub fn get_pressure_amount(&self) -> Modulated<f32> {
+        self.pressure_amount.load()
+    }
+
+    pub fn get_pitch_bend_range(&self) -> Modulated<f32> {
+        self.pitch_bend_range.load()
+    }
+
+    pub fn get_pan(&self) -> Modulated<f32> {
+        self.pan.load()
+    }
+
+    pub fn get_width(&self) -> Modulated<f32> {
+        self.width.load()
+    }
+
+    pub fn get_unison_voices(&self) -> usize {
+        self.unison_voices.load().value.round() as usize
+    }
+
+    pub fn get_unison_detune(&self) -> Modulated<f32> {
+        self.unison_detune.load()
+    }
+
+    pub fn get_unison_spread(&self) -> Modulated<f32> {
+        self.unison_spread.load()
+    }
+
+    pub fn get_sub_level(&self) -> Modulated<f32> {
+        self.sub_level.load()
+    }
+
+    pub fn get_sub_octave(&self) -> i32 {
+        self.sub_octave.load().value.round() as i32
+    }
+
+    pub fn get_pulse_width(&self) -> Modulated<f32> {
+        self.pulse_width.load()
+    }
+
+    pub fn get_filter_mode(&self) -> FilterMode {
+        FilterMode::from_value(self.filter_mode.load().value)
+    }
+
+    pub fn get_filter_slope(&self) -> FilterSlope {
+        FilterSlope::from_value(self.filter_slope.load().value)
+    }
+
+    pub fn get_filter_cutoff(&self) -> Modulated<f32> {
+        self.filter_cutoff.load()
+    }
+
+    pub fn get_filter_resonance(&self) -> Modulated<f32> {
+        self.filter_resonance.load()
+    }
+
+    pub fn get_filter_key_tracking(&self) -> Modulated<f32> {
+        self.filter_key_tracking.load()
+    }
+
+    pub fn get_mod_env(&self) -> ADSR<Modulated<f32>> {
+        self.mod_env.load()
+    }
+
+    pub fn get_mod_env_destination(&self) -> ModDestination {
+        ModDestination::from_value(self.mod_env_destination.load().value)
+    }
+
+    pub fn get_mod_env_amount(&self) -> Modulated<f32> {
+        self.mod_env_amount.load()
+    }
+
+    pub fn get_voice_lfo(&self) -> Lfo<Modulated<f32>> {
+        self.voice_lfo.load()
+    }
+
+    pub fn get_global_lfo(&self) -> Lfo<Modulated<f32>> {
+        self.global_lfo.load()
     }
 
-    pub fn get_hf_rolloff(&self) -> Modulated<f32> {
-        *self.hf_rolloff.read().unwrap()
+    pub fn get_mod_matrix(&self) -> [ModSlot<Modulated<f32>>; MOD_MATRIX_SLOTS] {
+        self.mod_matrix.load()
     }
 
     repetitive! {
@@ -149,15 +592,127 @@ impl SchoffhauzerSynthPluginParams {
             pub fn @['handle_param_ event_name '_event](&self, event: &@event_type) {
                 match event.param_id() {
                     __ if __ == Some(Self::VOLUME.id) => {
-                        self.volume.write().unwrap().@ty = DB(event.@event_method() as f32);
+                        self.volume.update(|it| it.@ty = DB(event.@event_method() as f32));
                     }
                     @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
                         __ if __ == Some(Self::ADSR.@field.id) => {
-                            self.adsr.write().unwrap().@field.@ty = event.@event_method() as f32;
+                            self.adsr.update(|it| it.@field.@ty = event.@event_method() as f32);
                         }
                     }
-                    __ if __ == Some(Self::HF_ROLLOFF.id) => {
-                        self.hf_rolloff.write().unwrap().@ty = event.@event_method() as f32;
+                    @for osc in [0, 1, 2] {
+                        @for field in ['waveform, 'octave, 'semitone, 'fine, 'level, 'hf_rolloff] {
+                            __ if __ == Some(Self::OSCILLATORS[@osc].@field.id) => {
+                                self.oscillators.update(|it| it[@osc].@field.@ty = event.@event_method() as f32);
+                            }
+                        }
+                    }
+                    @for link in [0, 1] {
+                        @for field in ['sync, 'ring] {
+                            __ if __ == Some(Self::OSCILLATOR_LINKS[@link].@field.id) => {
+                                self.oscillator_links.update(|it| it[@link].@field.@ty = event.@event_method() as f32);
+                            }
+                        }
+                    }
+                    __ if __ == Some(Self::VELOCITY_AMOUNT.id) => {
+                        self.velocity_amount.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::VELOCITY_CURVE.id) => {
+                        self.velocity_curve.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::VELOCITY_HF_ROLLOFF.id) => {
+                        self.velocity_hf_rolloff.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::VELOCITY_RELEASE.id) => {
+                        self.velocity_release.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::MAX_POLYPHONY.id) => {
+                        self.max_polyphony.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::VOICE_STEALING.id) => {
+                        self.voice_stealing.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PLAY_MODE.id) => {
+                        self.play_mode.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::NOTE_PRIORITY.id) => {
+                        self.note_priority.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PORTAMENTO.id) => {
+                        self.portamento.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PRESSURE_DESTINATION.id) => {
+                        self.pressure_destination.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PRESSURE_AMOUNT.id) => {
+                        self.pressure_amount.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PITCH_BEND_RANGE.id) => {
+                        self.pitch_bend_range.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PAN.id) => {
+                        self.pan.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::WIDTH.id) => {
+                        self.width.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::UNISON_VOICES.id) => {
+                        self.unison_voices.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::UNISON_DETUNE.id) => {
+                        self.unison_detune.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::UNISON_SPREAD.id) => {
+                        self.unison_spread.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::SUB_LEVEL.id) => {
+                        self.sub_level.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::SUB_OCTAVE.id) => {
+                        self.sub_octave.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::PULSE_WIDTH.id) => {
+                        self.pulse_width.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::FILTER_MODE.id) => {
+                        self.filter_mode.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::FILTER_SLOPE.id) => {
+                        self.filter_slope.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::FILTER_CUTOFF.id) => {
+                        self.filter_cutoff.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::FILTER_RESONANCE.id) => {
+                        self.filter_resonance.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::FILTER_KEY_TRACKING.id) => {
+                        self.filter_key_tracking.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                        __ if __ == Some(Self::MOD_ENV.@field.id) => {
+                            self.mod_env.update(|it| it.@field.@ty = event.@event_method() as f32);
+                        }
+                    }
+                    __ if __ == Some(Self::MOD_ENV_DESTINATION.id) => {
+                        self.mod_env_destination.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    __ if __ == Some(Self::MOD_ENV_AMOUNT.id) => {
+                        self.mod_env_amount.update(|it| it.@ty = event.@event_method() as f32);
+                    }
+                    @for field in ['shape, 'rate, 'tempo_sync, 'sync_rate, 'retrigger, 'fade_in, 'destination, 'amount] {
+                        __ if __ == Some(Self::VOICE_LFO.@field.id) => {
+                            self.voice_lfo.update(|it| it.@field.@ty = event.@event_method() as f32);
+                        }
+                        __ if __ == Some(Self::GLOBAL_LFO.@field.id) => {
+                            self.global_lfo.update(|it| it.@field.@ty = event.@event_method() as f32);
+                        }
+                    }
+                    @for slot in [0, 1, 2, 3, 4, 5, 6, 7] {
+                        @for field in ['source, 'via, 'destination, 'amount] {
+                            __ if __ == Some(Self::MOD_MATRIX[@slot].@field.id) => {
+                                self.mod_matrix.update(|it| it[@slot].@field.@ty = event.@event_method() as f32);
+                            }
+                        }
                     }
                     _ => {}
                 }
@@ -173,11 +728,245 @@ impl SchoffhauzerSynthPluginParams {
         }
         true
     }
+
+    /// Plain value of `param_id`, without modulation.
+    pub fn get_value(&self, param_id: ClapId) -> Option<f64> {
+        repetitive! {
+            match param_id {
+                __ if __ == Some(Self::VOLUME.id) => {
+                    Some(self.get_volume().value.db() as f64)
+                }
+                @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                    __ if __ == Some(Self::ADSR.@field.id) => {
+                        Some(self.get_adsr().@field.value as f64)
+                    }
+                }
+                @for osc in [0, 1, 2] {
+                    @for field in ['waveform, 'octave, 'semitone, 'fine, 'level, 'hf_rolloff] {
+                        __ if __ == Some(Self::OSCILLATORS[@osc].@field.id) => {
+                            Some(self.get_oscillators()[@osc].@field.value as f64)
+                        }
+                    }
+                }
+                @for link in [0, 1] {
+                    @for field in ['sync, 'ring] {
+                        __ if __ == Some(Self::OSCILLATOR_LINKS[@link].@field.id) => {
+                            Some(self.get_oscillator_links()[@link].@field.value as f64)
+                        }
+                    }
+                }
+                __ if __ == Some(Self::VELOCITY_AMOUNT.id) => {
+                    Some(self.get_velocity_amount().value as f64)
+                }
+                __ if __ == Some(Self::VELOCITY_CURVE.id) => {
+                    Some(self.get_velocity_curve().value() as f64)
+                }
+                __ if __ == Some(Self::VELOCITY_HF_ROLLOFF.id) => {
+                    Some(self.get_velocity_hf_rolloff().value as f64)
+                }
+                __ if __ == Some(Self::VELOCITY_RELEASE.id) => {
+                    Some(self.get_velocity_release().value as f64)
+                }
+                __ if __ == Some(Self::MAX_POLYPHONY.id) => {
+                    Some(self.get_max_polyphony() as f64)
+                }
+                __ if __ == Some(Self::VOICE_STEALING.id) => {
+                    Some(self.get_voice_stealing().value() as f64)
+                }
+                __ if __ == Some(Self::PLAY_MODE.id) => {
+                    Some(self.get_play_mode().value() as f64)
+                }
+                __ if __ == Some(Self::NOTE_PRIORITY.id) => {
+                    Some(self.get_note_priority().value() as f64)
+                }
+                __ if __ == Some(Self::PORTAMENTO.id) => {
+                    Some(self.get_portamento().value as f64)
+                }
+                __ if __ == Some(Self::PRESSURE_DESTINATION.id) => {
+                    Some(self.get_pressure_destination().value() as f64)
+                }
+                __ if __ == Some(Self::PRESSURE_AMOUNT.id) => {
+                    Some(self.get_pressure_amount().value as f64)
+                }
+                __ if __ == Some(Self::PITCH_BEND_RANGE.id) => {
+                    Some(self.get_pitch_bend_range().value as f64)
+                }
+                __ if __ == Some(Self::PAN.id) => {
+                    Some(self.get_pan().value as f64)
+                }
+                __ if __ == Some(Self::WIDTH.id) => {
+                    Some(self.get_width().value as f64)
+                }
+                __ if __ == Some(Self::UNISON_VOICES.id) => {
+                    Some(self.get_unison_voices() as f64)
+                }
+                __ if __ == Some(Self::UNISON_DETUNE.id) => {
+                    Some(self.get_unison_detune().value as f64)
+                }
+                __ if __ == Some(Self::UNISON_SPREAD.id) => {
+                    Some(self.get_unison_spread().value as f64)
+                }
+                __ if __ == Some(Self::SUB_LEVEL.id) => {
+                    Some(self.get_sub_level().value as f64)
+                }
+                __ if __ == Some(Self::SUB_OCTAVE.id) => {
+                    Some(self.get_sub_octave() as f64)
+                }
+                __ if __ == Some(Self::PULSE_WIDTH.id) => {
+                    Some(self.get_pulse_width().value as f64)
+                }
+                __ if __ == Some(Self::FILTER_MODE.id) => {
+                    Some(self.get_filter_mode().value() as f64)
+                }
+                __ if __ == Some(Self::FILTER_SLOPE.id) => {
+                    Some(self.get_filter_slope().value() as f64)
+                }
+                __ if __ == Some(Self::FILTER_CUTOFF.id) => {
+                    Some(self.get_filter_cutoff().value as f64)
+                }
+                __ if __ == Some(Self::FILTER_RESONANCE.id) => {
+                    Some(self.get_filter_resonance().value as f64)
+                }
+                __ if __ == Some(Self::FILTER_KEY_TRACKING.id) => {
+                    Some(self.get_filter_key_tracking().value as f64)
+                }
+                @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                    __ if __ == Some(Self::MOD_ENV.@field.id) => {
+                        Some(self.get_mod_env().@field.value as f64)
+                    }
+                }
+                __ if __ == Some(Self::MOD_ENV_DESTINATION.id) => {
+                    Some(self.get_mod_env_destination().value() as f64)
+                }
+                __ if __ == Some(Self::MOD_ENV_AMOUNT.id) => {
+                    Some(self.get_mod_env_amount().value as f64)
+                }
+                @for field in ['shape, 'rate, 'tempo_sync, 'sync_rate, 'retrigger, 'fade_in, 'destination, 'amount] {
+                    __ if __ == Some(Self::VOICE_LFO.@field.id) => {
+                        Some(self.get_voice_lfo().@field.value as f64)
+                    }
+                    __ if __ == Some(Self::GLOBAL_LFO.@field.id) => {
+                        Some(self.get_global_lfo().@field.value as f64)
+                    }
+                }
+                @for slot in [0, 1, 2, 3, 4, 5, 6, 7] {
+                    @for field in ['source, 'via, 'destination, 'amount] {
+                        __ if __ == Some(Self::MOD_MATRIX[@slot].@field.id) => {
+                            Some(self.get_mod_matrix()[@slot].@field.value as f64)
+                        }
+                    }
+                }
+                _ => None,
+            }
+        }
+    }
+
+    pub fn value_to_text(param_id: ClapId, value: f64, writer: &mut impl Write) -> std::fmt::Result {
+        repetitive! {
+            match param_id {
+                __ if __ == Some(Self::VOLUME.id) => write!(writer, "{value:+.2}dB"),
+                @for envelope in ['ADSR, 'MOD_ENV] {
+                    @for p in ['attack_duration, 'decay_duration, 'release_duration] {
+                        __ if __ == Some(Self::@envelope.@p.id) => write!(writer, "{value:+.2}s"),
+                    }
+                    @for p in ['attack_power, 'decay_power, 'sustain, 'release_power] {
+                        __ if __ == Some(Self::@envelope.@p.id) => write!(writer, "{value:+.2}"),
+                    }
+                }
+                __ if __ == Some(Self::MOD_ENV_DESTINATION.id) => {
+                    write!(writer, "{}", ModDestination::from_value(value as f32))
+                }
+                @for p in ['VELOCITY_AMOUNT, 'VELOCITY_HF_ROLLOFF, 'VELOCITY_RELEASE, 'PRESSURE_AMOUNT, 'PAN, 'WIDTH, 'UNISON_SPREAD, 'SUB_LEVEL, 'PULSE_WIDTH, 'MOD_ENV_AMOUNT, 'FILTER_RESONANCE, 'FILTER_KEY_TRACKING] {
+                    __ if __ == Some(Self::@p.id) => write!(writer, "{:+.2}%", value * 100.0),
+                }
+                @for osc in [0, 1, 2] {
+                    __ if __ == Some(Self::OSCILLATORS[@osc].waveform.id) => {
+                        write!(writer, "{}", Waveform::from_value(value as f32))
+                    }
+                    __ if __ == Some(Self::OSCILLATORS[@osc].octave.id) => write!(writer, "{value:+.0}oct"),
+                    __ if __ == Some(Self::OSCILLATORS[@osc].semitone.id) => write!(writer, "{value:+.0}st"),
+                    __ if __ == Some(Self::OSCILLATORS[@osc].fine.id) => write!(writer, "{value:+.1}ct"),
+                    @for p in ['level, 'hf_rolloff] {
+                        __ if __ == Some(Self::OSCILLATORS[@osc].@p.id) => write!(writer, "{:+.2}%", value * 100.0),
+                    }
+                }
+                @for link in [0, 1] {
+                    __ if __ == Some(Self::OSCILLATOR_LINKS[@link].sync.id) => {
+                        write!(writer, "{}", if value >= 0.5 { "On" } else { "Off" })
+                    }
+                    __ if __ == Some(Self::OSCILLATOR_LINKS[@link].ring.id) => write!(writer, "{:+.2}%", value * 100.0),
+                }
+                __ if __ == Some(Self::VELOCITY_CURVE.id) => {
+                    write!(writer, "{}", VelocityCurve::from_value(value as f32))
+                }
+                __ if __ == Some(Self::MAX_POLYPHONY.id) => write!(writer, "{value:.0}"),
+                __ if __ == Some(Self::VOICE_STEALING.id) => {
+                    write!(writer, "{}", VoiceStealing::from_value(value as f32))
+                }
+                __ if __ == Some(Self::PLAY_MODE.id) => {
+                    write!(writer, "{}", PlayMode::from_value(value as f32))
+                }
+                __ if __ == Some(Self::NOTE_PRIORITY.id) => {
+                    write!(writer, "{}", NotePriority::from_value(value as f32))
+                }
+                __ if __ == Some(Self::PORTAMENTO.id) => write!(writer, "{value:.3}s"),
+                __ if __ == Some(Self::PRESSURE_DESTINATION.id) => {
+                    write!(writer, "{}", PressureDestination::from_value(value as f32))
+                }
+                __ if __ == Some(Self::PITCH_BEND_RANGE.id) => write!(writer, "{value:.0}st"),
+                __ if __ == Some(Self::UNISON_VOICES.id) => write!(writer, "{value:.0}"),
+                __ if __ == Some(Self::UNISON_DETUNE.id) => write!(writer, "{value:.1}ct"),
+                __ if __ == Some(Self::SUB_OCTAVE.id) => write!(writer, "{value:+.0}oct"),
+                __ if __ == Some(Self::FILTER_MODE.id) => {
+                    write!(writer, "{}", FilterMode::from_value(value as f32))
+                }
+                __ if __ == Some(Self::FILTER_SLOPE.id) => {
+                    write!(writer, "{}", FilterSlope::from_value(value as f32))
+                }
+                __ if __ == Some(Self::FILTER_CUTOFF.id) => write!(writer, "{:.0}Hz", MidiNote(value as f32).freq()),
+                @for lfo in ['VOICE_LFO, 'GLOBAL_LFO] {
+                    __ if __ == Some(Self::@lfo.shape.id) => {
+                        write!(writer, "{}", LfoShape::from_value(value as f32))
+                    }
+                    __ if __ == Some(Self::@lfo.rate.id) => write!(writer, "{value:.2}Hz"),
+                    __ if __ == Some(Self::@lfo.tempo_sync.id) => {
+                        write!(writer, "{}", if value >= 0.5 { "On" } else { "Off" })
+                    }
+                    __ if __ == Some(Self::@lfo.sync_rate.id) => {
+                        write!(writer, "{}", LfoSyncRate::from_value(value as f32))
+                    }
+                    __ if __ == Some(Self::@lfo.retrigger.id) => {
+                        write!(writer, "{}", LfoRetrigger::from_value(value as f32))
+                    }
+                    __ if __ == Some(Self::@lfo.fade_in.id) => write!(writer, "{value:.2}s"),
+                    __ if __ == Some(Self::@lfo.destination.id) => {
+                        write!(writer, "{}", ModDestination::from_value(value as f32))
+                    }
+                    __ if __ == Some(Self::@lfo.amount.id) => write!(writer, "{:+.2}%", value * 100.0),
+                }
+                @for slot in [0, 1, 2, 3, 4, 5, 6, 7] {
+                    @for p in ['source, 'via] {
+                        __ if __ == Some(Self::MOD_MATRIX[@slot].@p.id) => {
+                            write!(writer, "{}", ModSource::from_value(value as f32))
+                        }
+                    }
+                    __ if __ == Some(Self::MOD_MATRIX[@slot].destination.id) => {
+                        match destination_from_value(value as f32) {
+                            Some(info) => write!(writer, "{}", display_name(info)),
+                            None => write!(writer, "Off"),
+                        }
+                    }
+                    __ if __ == Some(Self::MOD_MATRIX[@slot].amount.id) => write!(writer, "{:+.2}%", value * 100.0),
+                }
+                _ => Err(std::fmt::Error),
+            }
+        }
+    }
 }
 
 impl<'a> PluginMainThreadParams for SchoffhauzerSynthPluginMainThread<'a> {
     fn count(&mut self) -> u32 {
-        1 + 7 + 1
+        1 + 7 + 4 + 2 + 3 + 2 + 1 + 2 + 3 + 3 * 6 + 2 + 1 + 2 * 2 + 5 + 7 + 2 + 2 * 8 + 8 * 4
     }
 
     fn get_info(&mut self, param_index: u32, info: &mut ParamInfoWriter) {
@@ -193,54 +982,226 @@ impl<'a> PluginMainThreadParams for SchoffhauzerSynthPluginMainThread<'a> {
             }
         }
         if param_index == i.next().unwrap() {
-            info.set(Params::HF_ROLLOFF);
+            info.set(Params::VELOCITY_AMOUNT);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::VELOCITY_CURVE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::VELOCITY_HF_ROLLOFF);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::VELOCITY_RELEASE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::MAX_POLYPHONY);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::VOICE_STEALING);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PLAY_MODE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::NOTE_PRIORITY);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PORTAMENTO);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PRESSURE_DESTINATION);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PRESSURE_AMOUNT);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PITCH_BEND_RANGE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PAN);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::WIDTH);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::UNISON_VOICES);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::UNISON_DETUNE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::UNISON_SPREAD);
+        }
+        for osc in &Params::OSCILLATORS {
+            repetitive! {
+                @for field in ['waveform, 'octave, 'semitone, 'fine, 'level, 'hf_rolloff] {
+                    if param_index == i.next().unwrap() {
+                        info.set(osc.@field);
+                    }
+                }
+            }
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::SUB_LEVEL);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::SUB_OCTAVE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::PULSE_WIDTH);
+        }
+        for link in &Params::OSCILLATOR_LINKS {
+            if param_index == i.next().unwrap() {
+                info.set(link.sync);
+            }
+            if param_index == i.next().unwrap() {
+                info.set(link.ring);
+            }
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::FILTER_MODE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::FILTER_SLOPE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::FILTER_CUTOFF);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::FILTER_RESONANCE);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::FILTER_KEY_TRACKING);
         }
-    }
-
-    fn get_value(&mut self, param_id: ClapId) -> Option<f64> {
         repetitive! {
-            match param_id {
-                __ if __ == Some(Params::VOLUME.id) => {
-                    Some(self.shared.params.get_volume().value.db() as f64)
+            @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                if param_index == i.next().unwrap() {
+                    info.set(Params::MOD_ENV.@field);
                 }
-                @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
-                    __ if __ == Some(Params::ADSR.@field.id) => {
-                        Some(self.shared.params.get_adsr().@field.value as f64)
+            }
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::MOD_ENV_DESTINATION);
+        }
+        if param_index == i.next().unwrap() {
+            info.set(Params::MOD_ENV_AMOUNT);
+        }
+        for lfo in &LFOS {
+            repetitive! {
+                @for field in ['shape, 'rate, 'tempo_sync, 'sync_rate, 'retrigger, 'fade_in, 'destination, 'amount] {
+                    if param_index == i.next().unwrap() {
+                        info.set(lfo.@field);
                     }
                 }
-                __ if __ == Some(Params::HF_ROLLOFF.id) => {
-                    Some(self.shared.params.get_hf_rolloff().value as f64)
+            }
+        }
+        for slot in &Params::MOD_MATRIX {
+            repetitive! {
+                @for field in ['source, 'via, 'destination, 'amount] {
+                    if param_index == i.next().unwrap() {
+                        info.set(slot.@field);
+                    }
                 }
-                _ => None,
             }
         }
     }
 
+    fn get_value(&mut self, param_id: ClapId) -> Option<f64> {
+        self.shared.params.get_value(param_id)
+    }
+
     fn value_to_text(
         &mut self,
         param_id: ClapId,
         value: f64,
         writer: &mut ParamDisplayWriter,
     ) -> std::fmt::Result {
-        repetitive! {
-            match param_id {
-                __ if __ == Some(Params::VOLUME.id) => write!(writer, "{value:+.2}dB"),
-                @for p in ['attack_duration, 'decay_duration, 'release_duration] {
-                    __ if __ == Some(Params::ADSR.@p.id) => write!(writer, "{value:+.2}s"),
-                }
-                @for p in ['attack_power, 'decay_power, 'sustain, 'release_power] {
-                    __ if __ == Some(Params::ADSR.@p.id) => write!(writer, "{value:+.2}"),
-                }
-                __ if __ == Some(Params::HF_ROLLOFF.id) => write!(writer, "{:+.2}%", value * 100.0),
-                _ => Err(std::fmt::Error),
-            }
-        }
+        Params::value_to_text(param_id, value, writer)
     }
 
     fn text_to_value(&mut self, param_id: ClapId, text: &CStr) -> Option<f64> {
         let text = text.to_str().unwrap();
         Some(match param_id {
-            __ if __ == Some(Params::HF_ROLLOFF.id) => f64::from_str(text).ok()? / 100.0,
+            __ if __ == Some(Params::HF_ROLLOFF.id)
+                || __ == Some(Params::VELOCITY_AMOUNT.id)
+                || __ == Some(Params::VELOCITY_HF_ROLLOFF.id)
+                || __ == Some(Params::VELOCITY_RELEASE.id)
+                || __ == Some(Params::PRESSURE_AMOUNT.id)
+                || __ == Some(Params::PAN.id)
+                || __ == Some(Params::WIDTH.id)
+                || __ == Some(Params::UNISON_SPREAD.id)
+                || __ == Some(Params::SUB_LEVEL.id)
+                || Params::OSCILLATORS.iter().any(|osc| __ == Some(osc.level.id) || __ == Some(osc.hf_rolloff.id))
+                || __ == Some(Params::PULSE_WIDTH.id)
+                || Params::OSCILLATOR_LINKS.iter().any(|link| __ == Some(link.ring.id))
+                || __ == Some(Params::FILTER_RESONANCE.id)
+                || __ == Some(Params::FILTER_KEY_TRACKING.id)
+                || __ == Some(Params::MOD_ENV_AMOUNT.id)
+                || LFOS.iter().any(|lfo| __ == Some(lfo.amount.id))
+                || Params::MOD_MATRIX.iter().any(|slot| __ == Some(slot.amount.id)) =>
+            {
+                f64::from_str(text.trim_end_matches('%')).ok()? / 100.0
+            }
+            __ if __ == Some(Params::VELOCITY_CURVE.id) => VelocityCurve::text_to_value(text)?,
+            __ if __ == Some(Params::VOICE_STEALING.id) => VoiceStealing::text_to_value(text)?,
+            __ if __ == Some(Params::PLAY_MODE.id) => PlayMode::text_to_value(text)?,
+            __ if __ == Some(Params::NOTE_PRIORITY.id) => NotePriority::text_to_value(text)?,
+            __ if __ == Some(Params::PORTAMENTO.id) || LFOS.iter().any(|lfo| __ == Some(lfo.fade_in.id)) => {
+                f64::from_str(text.trim_end_matches('s')).ok()?
+            }
+            __ if __ == Some(Params::PRESSURE_DESTINATION.id) => PressureDestination::text_to_value(text)?,
+            __ if __ == Some(Params::MOD_ENV_DESTINATION.id)
+                || LFOS.iter().any(|lfo| __ == Some(lfo.destination.id)) =>
+            {
+                ModDestination::text_to_value(text)?
+            }
+            __ if __ == Some(Params::PITCH_BEND_RANGE.id) => f64::from_str(text.trim_end_matches("st")).ok()?,
+            __ if __ == Some(Params::UNISON_DETUNE.id)
+                || Params::OSCILLATORS.iter().any(|osc| __ == Some(osc.fine.id)) =>
+            {
+                f64::from_str(text.trim_end_matches("ct")).ok()?
+            }
+            __ if __ == Some(Params::SUB_OCTAVE.id)
+                || Params::OSCILLATORS.iter().any(|osc| __ == Some(osc.octave.id)) =>
+            {
+                f64::from_str(text.trim_end_matches("oct")).ok()?
+            }
+            __ if Params::OSCILLATORS.iter().any(|osc| __ == Some(osc.waveform.id)) => Waveform::text_to_value(text)?,
+            __ if Params::OSCILLATOR_LINKS.iter().any(|link| __ == Some(link.sync.id))
+                || LFOS.iter().any(|lfo| __ == Some(lfo.tempo_sync.id)) =>
+            {
+                match text.trim() {
+                    on if on.eq_ignore_ascii_case("on") => 1.0,
+                    off if off.eq_ignore_ascii_case("off") => 0.0,
+                    text => f64::from_str(text).ok()?,
+                }
+            }
+            __ if Params::OSCILLATORS.iter().any(|osc| __ == Some(osc.semitone.id)) => {
+                f64::from_str(text.trim_end_matches("st")).ok()?
+            }
+            __ if __ == Some(Params::FILTER_MODE.id) => FilterMode::text_to_value(text)?,
+            __ if __ == Some(Params::FILTER_SLOPE.id) => FilterSlope::text_to_value(text)?,
+            __ if __ == Some(Params::FILTER_CUTOFF.id) => {
+                let freq = f32::from_str(text.trim_end_matches("Hz")).ok()?;
+                MidiNote::<f32>::try_from_freq(freq).ok()?.midi() as f64
+            }
+            __ if LFOS.iter().any(|lfo| __ == Some(lfo.shape.id)) => LfoShape::text_to_value(text)?,
+            __ if LFOS.iter().any(|lfo| __ == Some(lfo.rate.id)) => f64::from_str(text.trim_end_matches("Hz")).ok()?,
+            __ if LFOS.iter().any(|lfo| __ == Some(lfo.sync_rate.id)) => LfoSyncRate::text_to_value(text)?,
+            __ if LFOS.iter().any(|lfo| __ == Some(lfo.retrigger.id)) => LfoRetrigger::text_to_value(text)?,
+            __ if Params::MOD_MATRIX.iter().any(|slot| __ == Some(slot.source.id) || __ == Some(slot.via.id)) => {
+                ModSource::text_to_value(text)?
+            }
+            __ if Params::MOD_MATRIX.iter().any(|slot| __ == Some(slot.destination.id)) => {
+                match Params::MOD_MATRIX_DESTINATIONS
+                    .iter()
+                    .position(|info| display_name(info).eq_ignore_ascii_case(text.trim()))
+                {
+                    Some(index) => (index + 1) as f64,
+                    None if text.trim().eq_ignore_ascii_case("off") => 0.0,
+                    None => f64::from_str(text).ok()?,
+                }
+            }
             _ => f64::from_str(text).ok()?,
         })
     }
@@ -248,13 +1209,19 @@ impl<'a> PluginMainThreadParams for SchoffhauzerSynthPluginMainThread<'a> {
     fn flush(
         &mut self,
         input_parameter_changes: &InputEvents,
-        _output_parameter_changes: &mut OutputEvents,
+        output_parameter_changes: &mut OutputEvents,
     ) {
         for event in input_parameter_changes {
             if self.shared.params.handle_event(event) {
                 continue;
             }
         }
+        // Left for whichever flush or process currently holds the queue
+        if let Some(mut changes) = self.shared.param_changes.consumer() {
+            for change in changes.drain() {
+                change.push_to(output_parameter_changes);
+            }
+        }
     }
 }
 
@@ -262,12 +1229,18 @@ impl<'a> PluginAudioProcessorParams for SchoffhauzerSynthAudioProcessor<'a> {
     fn flush(
         &mut self,
         input_parameter_changes: &InputEvents,
-        _output_parameter_changes: &mut OutputEvents,
+        output_parameter_changes: &mut OutputEvents,
     ) {
         for event in input_parameter_changes {
             if self.shared.params.handle_event(event) {
                 continue;
             }
         }
+        // Left for whichever flush or process currently holds the queue
+        if let Some(mut changes) = self.shared.param_changes.consumer() {
+            for change in changes.drain() {
+                change.push_to(output_parameter_changes);
+            }
+        }
     }
 }
@@ -1,35 +1,205 @@
 use crate::SchoffhauzerSynthPluginMainThread;
+use crate::params::SchoffhauzerSynthPluginParams;
 use crate::utils::db::DB;
 use crate::utils::modulated::Modulated;
+use clack_extensions::params::ParamRescanFlags;
 use clack_extensions::state::PluginStateImpl;
 use clack_plugin::plugin::PluginError;
 use clack_plugin::stream::{InputStream, OutputStream};
+use serde_json::Value;
+use std::io;
+use crate::synth::filter::{FilterMode, FilterSlope};
+use crate::synth::lfo::Lfo;
+use crate::synth::mod_matrix::{MOD_MATRIX_SLOTS, ModSlot};
+use crate::synth::modulation::ModDestination;
+use crate::synth::note_expression::PressureDestination;
+use crate::synth::note_stack::NotePriority;
+use crate::synth::oscillator::{OSCILLATOR_COUNT, Oscillator, OscillatorLink};
+use crate::synth::poly_synth::{PlayMode, VoiceStealing};
 use crate::utils::envelope::ADSR;
+use crate::utils::param_enum::ParamEnum;
+use crate::utils::velocity::VelocityCurve;
 
+/// Fields missing from older states fall back to the parameter defaults, see [`Self::from_reader`].
 #[derive_aliases::derive(..SerDe)]
-struct SchoffhauzerSynthPluginState {
+#[serde(default)]
+pub(crate) struct SchoffhauzerSynthPluginState {
     volume: DB<f32>,
     adsr: ADSR<f32>,
-    hf_rolloff: f32,
+    /// The first states stored OSC 1's rolloff at the top level, before it moved into `oscillators`
+    #[serde(rename = "hf_rolloff", skip_serializing)]
+    legacy_hf_rolloff: Option<f32>,
+    oscillators: [Oscillator<f32>; OSCILLATOR_COUNT],
+    oscillator_links: [OscillatorLink<f32>; OSCILLATOR_COUNT - 1],
+    velocity_amount: f32,
+    velocity_curve: VelocityCurve,
+    velocity_hf_rolloff: f32,
+    velocity_release: f32,
+    max_polyphony: usize,
+    voice_stealing: VoiceStealing,
+    play_mode: PlayMode,
+    note_priority: NotePriority,
+    portamento: f32,
+    pressure_destination: PressureDestination,
+    pressure_amount: f32,
+    pitch_bend_range: f32,
+    pan: f32,
+    width: f32,
+    unison_voices: usize,
+    unison_detune: f32,
+    unison_spread: f32,
+    sub_level: f32,
+    sub_octave: i32,
+    pulse_width: f32,
+    filter_mode: FilterMode,
+    filter_slope: FilterSlope,
+    filter_cutoff: f32,
+    filter_resonance: f32,
+    filter_key_tracking: f32,
+    mod_env: ADSR<f32>,
+    mod_env_destination: ModDestination,
+    mod_env_amount: f32,
+    voice_lfo: Lfo<f32>,
+    global_lfo: Lfo<f32>,
+    mod_matrix: [ModSlot<f32>; MOD_MATRIX_SLOTS],
+}
+
+impl SchoffhauzerSynthPluginState {
+    pub(crate) fn from_params(params: &SchoffhauzerSynthPluginParams) -> Self {
+        Self {
+            volume: params.get_volume().value,
+            adsr: params.get_adsr().map(|it| it.value),
+            legacy_hf_rolloff: None,
+            oscillators: params.get_oscillators().map(|osc| osc.map(|it| it.value)),
+            oscillator_links: params.get_oscillator_links().map(|link| link.map(|it| it.value)),
+            velocity_amount: params.get_velocity_amount().value,
+            velocity_curve: params.get_velocity_curve(),
+            velocity_hf_rolloff: params.get_velocity_hf_rolloff().value,
+            velocity_release: params.get_velocity_release().value,
+            max_polyphony: params.get_max_polyphony(),
+            voice_stealing: params.get_voice_stealing(),
+            play_mode: params.get_play_mode(),
+            note_priority: params.get_note_priority(),
+            portamento: params.get_portamento().value,
+            pressure_destination: params.get_pressure_destination(),
+            pressure_amount: params.get_pressure_amount().value,
+            pitch_bend_range: params.get_pitch_bend_range().value,
+            pan: params.get_pan().value,
+            width: params.get_width().value,
+            unison_voices: params.get_unison_voices(),
+            unison_detune: params.get_unison_detune().value,
+            unison_spread: params.get_unison_spread().value,
+            sub_level: params.get_sub_level().value,
+            sub_octave: params.get_sub_octave(),
+            pulse_width: params.get_pulse_width().value,
+            filter_mode: params.get_filter_mode(),
+            filter_slope: params.get_filter_slope(),
+            filter_cutoff: params.get_filter_cutoff().value,
+            filter_resonance: params.get_filter_resonance().value,
+            filter_key_tracking: params.get_filter_key_tracking().value,
+            mod_env: params.get_mod_env().map(|it| it.value),
+            mod_env_destination: params.get_mod_env_destination(),
+            mod_env_amount: params.get_mod_env_amount().value,
+            voice_lfo: params.get_voice_lfo().map(|it| it.value),
+            global_lfo: params.get_global_lfo().map(|it| it.value),
+            mod_matrix: params.get_mod_matrix().map(|slot| slot.map(|it| it.value)),
+        }
+    }
+
+    /// Parses a saved state. Any field it lacks, including fields of the nested structs,
+    /// keeps its parameter default rather than the nested struct's zero default.
+    pub(crate) fn from_reader(reader: impl io::Read) -> serde_json::Result<Self> {
+        let mut state = serde_json::to_value(Self::default())?;
+        merge(&mut state, serde_json::from_reader(reader)?);
+        serde_json::from_value(state)
+    }
+
+    //noinspection RsUnwrap
+    pub(crate) fn apply_to(&self, params: &SchoffhauzerSynthPluginParams) {
+        let mut oscillators = self.oscillators;
+        if let Some(hf_rolloff) = self.legacy_hf_rolloff {
+            oscillators[0].hf_rolloff = hf_rolloff;
+        }
+        params.volume.store(Modulated::new(self.volume, DB(0.0)));
+        params.adsr.store(self.adsr.map(|&it| Modulated::new(it, 0.0)));
+        params.oscillators.store(oscillators.map(|osc| osc.map(|&it| Modulated::new(it, 0.0))));
+        params.oscillator_links.store(self.oscillator_links.map(|link| link.map(|&it| Modulated::new(it, 0.0))));
+        params.velocity_amount.store(Modulated::new(self.velocity_amount, 0.0));
+        params.velocity_curve.store(Modulated::new(self.velocity_curve.value(), 0.0));
+        params.velocity_hf_rolloff.store(Modulated::new(self.velocity_hf_rolloff, 0.0));
+        params.velocity_release.store(Modulated::new(self.velocity_release, 0.0));
+        params.max_polyphony.store(Modulated::new(self.max_polyphony as f32, 0.0));
+        params.voice_stealing.store(Modulated::new(self.voice_stealing.value(), 0.0));
+        params.play_mode.store(Modulated::new(self.play_mode.value(), 0.0));
+        params.note_priority.store(Modulated::new(self.note_priority.value(), 0.0));
+        params.portamento.store(Modulated::new(self.portamento, 0.0));
+        params.pressure_destination.store(Modulated::new(self.pressure_destination.value(), 0.0));
+        params.pressure_amount.store(Modulated::new(self.pressure_amount, 0.0));
+        params.pitch_bend_range.store(Modulated::new(self.pitch_bend_range, 0.0));
+        params.pan.store(Modulated::new(self.pan, 0.0));
+        params.width.store(Modulated::new(self.width, 0.0));
+        params.unison_voices.store(Modulated::new(self.unison_voices as f32, 0.0));
+        params.unison_detune.store(Modulated::new(self.unison_detune, 0.0));
+        params.unison_spread.store(Modulated::new(self.unison_spread, 0.0));
+        params.sub_level.store(Modulated::new(self.sub_level, 0.0));
+        params.sub_octave.store(Modulated::new(self.sub_octave as f32, 0.0));
+        params.pulse_width.store(Modulated::new(self.pulse_width, 0.0));
+        params.filter_mode.store(Modulated::new(self.filter_mode.value(), 0.0));
+        params.filter_slope.store(Modulated::new(self.filter_slope.value(), 0.0));
+        params.filter_cutoff.store(Modulated::new(self.filter_cutoff, 0.0));
+        params.filter_resonance.store(Modulated::new(self.filter_resonance, 0.0));
+        params.filter_key_tracking.store(Modulated::new(self.filter_key_tracking, 0.0));
+        params.mod_env.store(self.mod_env.map(|&it| Modulated::new(it, 0.0)));
+        params.mod_env_destination.store(Modulated::new(self.mod_env_destination.value(), 0.0));
+        params.mod_env_amount.store(Modulated::new(self.mod_env_amount, 0.0));
+        params.voice_lfo.store(self.voice_lfo.map(|&it| Modulated::new(it, 0.0)));
+        params.global_lfo.store(self.global_lfo.map(|&it| Modulated::new(it, 0.0)));
+        params.mod_matrix.store(self.mod_matrix.map(|slot| slot.map(|&it| Modulated::new(it, 0.0))));
+    }
+}
+
+/// Overwrites `base` with everything `overlay` has, recursing into objects and arrays.
+/// Array elements past the end of `base` are dropped, the state only has fixed size arrays.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(overlay)) => {
+            for (base, value) in base.iter_mut().zip(overlay) {
+                merge(base, value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+impl Default for SchoffhauzerSynthPluginState {
+    fn default() -> Self {
+        Self::from_params(&SchoffhauzerSynthPluginParams::default())
+    }
 }
 
 impl PluginStateImpl for SchoffhauzerSynthPluginMainThread<'_> {
     fn save(&mut self, output: &mut OutputStream) -> Result<(), PluginError> {
-        let state = SchoffhauzerSynthPluginState {
-            volume: self.shared.params.get_volume().value,
-            adsr: self.shared.params.get_adsr().map(|it| it.value),
-            hf_rolloff: self.shared.params.get_hf_rolloff().value,
-        };
+        let state = SchoffhauzerSynthPluginState::from_params(&self.shared.params);
         serde_json::to_writer(output, &state)?;
         Ok(())
     }
 
-    //noinspection RsUnwrap
     fn load(&mut self, input: &mut InputStream) -> Result<(), PluginError> {
-        let state = serde_json::from_reader::<_, SchoffhauzerSynthPluginState>(input)?;
-        *self.shared.params.volume.write().unwrap() = Modulated::new(state.volume, DB(0.0));
-        *self.shared.params.adsr.write().unwrap() = state.adsr.map(|&it| Modulated::new(it, 0.0));
-        *self.shared.params.hf_rolloff.write().unwrap() = Modulated::new(state.hf_rolloff, 0.0);
+        let state = SchoffhauzerSynthPluginState::from_reader(input)?;
+        state.apply_to(&self.shared.params);
+        if let Some(host_params) = self.host_params {
+            host_params.rescan(&mut self.host, ParamRescanFlags::VALUES);
+        }
         Ok(())
     }
 }
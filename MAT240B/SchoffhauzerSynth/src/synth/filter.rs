@@ -0,0 +1,141 @@
+use crate::utils::param_enum::ParamEnum;
+use derive_more::Display;
+use std::f32::consts::{PI, SQRT_2};
+
+/// Lowest damping, keeps full resonance just short of self-oscillation
+pub const MIN_DAMPING: f32 = 0.02;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum FilterMode {
+    /// Bypasses the filter
+    #[default]
+    Off,
+    #[display("Low Pass")]
+    LowPass,
+    #[display("Band Pass")]
+    BandPass,
+    #[display("High Pass")]
+    HighPass,
+    Notch,
+}
+
+impl ParamEnum for FilterMode {
+    const ALL: &'static [Self] = &[
+        FilterMode::Off,
+        FilterMode::LowPass,
+        FilterMode::BandPass,
+        FilterMode::HighPass,
+        FilterMode::Notch,
+    ];
+}
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum FilterSlope {
+    #[default]
+    #[display("12 dB")]
+    Db12,
+    /// Two stages, only the second one resonates
+    #[display("24 dB")]
+    Db24,
+}
+
+impl ParamEnum for FilterSlope {
+    const ALL: &'static [Self] = &[FilterSlope::Db12, FilterSlope::Db24];
+}
+
+#[derive_aliases::derive(..Copy, Debug)]
+struct Coefficients {
+    /// Damping, `2.0` is no resonance
+    k: f32,
+    a1: f32,
+    a2: f32,
+    a3: f32,
+}
+
+impl Coefficients {
+    fn new(g: f32, k: f32) -> Self {
+        let a1 = 1.0 / (1.0 + g * (g + k));
+        let a2 = g * a1;
+        let a3 = g * a2;
+        Self { k, a1, a2, a3 }
+    }
+}
+
+/// Zero-delay-feedback state variable filter after Andrew Simper, trapezoidal integrators.
+#[derive_aliases::derive(..Copy, Debug, Default)]
+struct Svf {
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+impl Svf {
+    fn process(&mut self, coefficients: &Coefficients, mode: FilterMode, v0: f32) -> f32 {
+        let Coefficients { k, a1, a2, a3 } = *coefficients;
+        let v3 = v0 - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+
+        match mode {
+            FilterMode::Off => v0,
+            FilterMode::LowPass => v2,
+            FilterMode::BandPass => v1,
+            FilterMode::HighPass => v0 - k * v1 - v2,
+            FilterMode::Notch => v0 - k * v1,
+        }
+    }
+}
+
+/// Stereo per-voice filter.
+pub struct Filter {
+    sample_rate: f32,
+    mode: FilterMode,
+    slope: FilterSlope,
+    /// `[first stage, second stage]`, the first stage only runs at 24 dB
+    coefficients: [Coefficients; 2],
+    /// `[left, right]` of each stage
+    stages: [[Svf; 2]; 2],
+}
+
+impl Filter {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            mode: FilterMode::Off,
+            slope: FilterSlope::Db12,
+            coefficients: [Coefficients::new(0.0, 2.0); 2],
+            stages: [[Svf::default(); 2]; 2],
+        }
+    }
+
+    /// `resonance` is in `0.0..=1.0`.
+    pub fn configure(&mut self, mode: FilterMode, slope: FilterSlope, cutoff: f32, resonance: f32) {
+        if mode != self.mode || slope != self.slope {
+            self.stages = [[Svf::default(); 2]; 2];
+        }
+        self.mode = mode;
+        self.slope = slope;
+
+        // Prewarped, and kept below Nyquist where `tan` blows up
+        let g = f32::tan(PI * cutoff.clamp(1.0, self.sample_rate * 0.49) / self.sample_rate);
+        let k = (2.0 * (1.0 - resonance.clamp(0.0, 1.0))).max(MIN_DAMPING);
+        self.coefficients = [Coefficients::new(g, SQRT_2), Coefficients::new(g, k)];
+    }
+
+    /// Filters the next `(left, right)` sample.
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if self.mode == FilterMode::Off {
+            return (left, right);
+        }
+        let [first, second] = &mut self.stages;
+        let mut samples = [left, right];
+        for (channel, sample) in samples.iter_mut().enumerate() {
+            if self.slope == FilterSlope::Db24 {
+                *sample = first[channel].process(&self.coefficients[0], self.mode, *sample);
+            }
+            *sample = second[channel].process(&self.coefficients[1], self.mode, *sample);
+        }
+        (samples[0], samples[1])
+    }
+}
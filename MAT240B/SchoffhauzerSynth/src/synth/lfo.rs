@@ -0,0 +1,301 @@
+use crate::synth::modulation::ModDestination;
+use crate::utils::atomic_param::F32Fields;
+use crate::utils::param_enum::ParamEnum;
+use core::fmt::Debug;
+use derive_more::Display;
+use repetitive::repetitive;
+use std::f32::consts::PI;
+
+/// Tempo of synced LFOs while the host doesn't report one, in beats per minute
+pub const FALLBACK_TEMPO: f64 = 120.0;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    /// Rising
+    Saw,
+    Square,
+    /// Holds a random value for each cycle
+    #[display("Sample & Hold")]
+    SampleAndHold,
+    /// Glides from one random value to the next over each cycle
+    #[display("Smooth Random")]
+    SmoothRandom,
+}
+
+impl ParamEnum for LfoShape {
+    const ALL: &'static [Self] = &[
+        LfoShape::Sine,
+        LfoShape::Triangle,
+        LfoShape::Saw,
+        LfoShape::Square,
+        LfoShape::SampleAndHold,
+        LfoShape::SmoothRandom,
+    ];
+}
+
+/// Length of one cycle while synced to the host tempo.
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum LfoSyncRate {
+    #[display("4/1")]
+    FourBars,
+    #[display("2/1")]
+    TwoBars,
+    #[display("1/1")]
+    Bar,
+    #[display("1/2")]
+    Half,
+    #[default]
+    #[display("1/4")]
+    Quarter,
+    #[display("1/4T")]
+    QuarterTriplet,
+    #[display("1/8")]
+    Eighth,
+    #[display("1/8T")]
+    EighthTriplet,
+    #[display("1/16")]
+    Sixteenth,
+    #[display("1/16T")]
+    SixteenthTriplet,
+    #[display("1/32")]
+    ThirtySecond,
+}
+
+impl ParamEnum for LfoSyncRate {
+    const ALL: &'static [Self] = &[
+        LfoSyncRate::FourBars,
+        LfoSyncRate::TwoBars,
+        LfoSyncRate::Bar,
+        LfoSyncRate::Half,
+        LfoSyncRate::Quarter,
+        LfoSyncRate::QuarterTriplet,
+        LfoSyncRate::Eighth,
+        LfoSyncRate::EighthTriplet,
+        LfoSyncRate::Sixteenth,
+        LfoSyncRate::SixteenthTriplet,
+        LfoSyncRate::ThirtySecond,
+    ];
+}
+
+impl LfoSyncRate {
+    /// In quarter notes, assuming 4/4.
+    pub fn beats(self) -> f64 {
+        match self {
+            LfoSyncRate::FourBars => 16.0,
+            LfoSyncRate::TwoBars => 8.0,
+            LfoSyncRate::Bar => 4.0,
+            LfoSyncRate::Half => 2.0,
+            LfoSyncRate::Quarter => 1.0,
+            LfoSyncRate::QuarterTriplet => 2.0 / 3.0,
+            LfoSyncRate::Eighth => 0.5,
+            LfoSyncRate::EighthTriplet => 1.0 / 3.0,
+            LfoSyncRate::Sixteenth => 0.25,
+            LfoSyncRate::SixteenthTriplet => 1.0 / 6.0,
+            LfoSyncRate::ThirtySecond => 0.125,
+        }
+    }
+}
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum LfoRetrigger {
+    /// Keeps running across notes, locked to the song position while synced to a playing host
+    #[default]
+    Free,
+    /// Restarts the cycle and the fade-in at every note on
+    Note,
+}
+
+impl ParamEnum for LfoRetrigger {
+    const ALL: &'static [Self] = &[LfoRetrigger::Free, LfoRetrigger::Note];
+}
+
+/// Settings of one LFO.
+#[derive_aliases::derive(..Copy, Debug, derive_more::Display, Default, ..SerDe)]
+#[display(bound(T: Debug))]
+#[display("{self:?}")]
+#[serde(default)]
+#[repr(C)]
+pub struct Lfo<T> {
+    /// Stepped, see [`LfoShape::from_value`]
+    pub shape: T,
+    /// In Hz, unless synced
+    pub rate: T,
+    /// Stepped, follows [`Lfo::sync_rate`] instead of [`Lfo::rate`]
+    pub tempo_sync: T,
+    /// Stepped, see [`LfoSyncRate::from_value`]
+    pub sync_rate: T,
+    /// Stepped, see [`LfoRetrigger::from_value`]
+    pub retrigger: T,
+    /// In seconds
+    pub fade_in: T,
+    /// Stepped, see [`ModDestination::from_value`]
+    pub destination: T,
+    pub amount: T,
+}
+
+unsafe impl<T: F32Fields> F32Fields for Lfo<T> {}
+const _: () = assert!(size_of::<Lfo<f32>>() == 8 * size_of::<f32>());
+
+impl<T> Lfo<T> {
+    pub fn map<R>(&self, mut f: impl FnMut(&T) -> R) -> Lfo<R> {
+        repetitive! {
+            Lfo {
+                @for field in ['shape, 'rate, 'tempo_sync, 'sync_rate, 'retrigger, 'fade_in, 'destination, 'amount] {
+                    @field: f(&self.@field),
+                }
+            }
+        }
+    }
+
+    pub fn map2<B, R>(&self, other: &Lfo<B>, mut f: impl FnMut(&T, &B) -> R) -> Lfo<R> {
+        repetitive! {
+            Lfo {
+                @for field in ['shape, 'rate, 'tempo_sync, 'sync_rate, 'retrigger, 'fade_in, 'destination, 'amount] {
+                    @field: f(&self.@field, &other.@field),
+                }
+            }
+        }
+    }
+}
+
+impl Lfo<f32> {
+    pub fn shape(&self) -> LfoShape {
+        LfoShape::from_value(self.shape)
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.tempo_sync >= 0.5
+    }
+
+    pub fn sync_rate(&self) -> LfoSyncRate {
+        LfoSyncRate::from_value(self.sync_rate)
+    }
+
+    pub fn retrigger(&self) -> LfoRetrigger {
+        LfoRetrigger::from_value(self.retrigger)
+    }
+
+    pub fn destination(&self) -> ModDestination {
+        ModDestination::from_value(self.destination)
+    }
+
+    /// In cycles per second, `tempo` is in beats per minute.
+    pub fn frequency(&self, tempo: f64) -> f32 {
+        if self.is_synced() {
+            (tempo / 60.0 / self.sync_rate().beats()) as f32
+        } else {
+            self.rate.max(0.0)
+        }
+    }
+
+    /// Where a free running LFO is in its cycle, from the song position in beats or else the seconds since activation.
+    pub fn free_phase(&self, song_position: Option<f64>, seconds: f64, tempo: f64) -> f32 {
+        let cycles = match song_position {
+            Some(beats) if self.is_synced() => beats / self.sync_rate().beats(),
+            _ => seconds * self.frequency(tempo) as f64,
+        };
+        cycles.rem_euclid(1.0) as f32
+    }
+}
+
+/// Running state of one LFO.
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct LfoInstance {
+    /// `0.0..1.0`
+    phase: f32,
+    rng: u32,
+    /// Random value of the current cycle, in `-1.0..=1.0`
+    random: f32,
+    last_random: f32,
+    /// Seconds since the last retrigger
+    elapsed: f32,
+}
+
+impl LfoInstance {
+    /// The random shapes are derived from `seed` so renders are repeatable.
+    pub fn new(seed: u32, phase: f32) -> Self {
+        let mut lfo = Self {
+            phase,
+            rng: seed.wrapping_mul(0x9e37_79b9) | 1,
+            random: 0.0,
+            last_random: 0.0,
+            elapsed: 0.0,
+        };
+        lfo.next_random();
+        lfo.next_random();
+        lfo
+    }
+
+    fn next_random(&mut self) {
+        // xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.last_random = self.random;
+        self.random = self.rng as f32 / u32::MAX as f32 * 2.0 - 1.0;
+    }
+
+    /// Restarts the cycle at `phase` and the fade-in.
+    pub fn retrigger(&mut self, phase: f32) {
+        self.phase = phase;
+        self.elapsed = 0.0;
+    }
+
+    /// Jumps to `phase`, a lower phase starts a new cycle.
+    pub fn sync(&mut self, phase: f32) {
+        if phase < self.phase {
+            self.next_random();
+        }
+        self.phase = phase;
+    }
+
+    /// Moves `cycles` forward over `seconds`.
+    pub fn advance(&mut self, cycles: f32, seconds: f32) {
+        self.phase += cycles;
+        while self.phase >= 1.0 {
+            self.phase -= 1.0;
+            self.next_random();
+        }
+        self.elapsed += seconds;
+    }
+
+    /// In `-1.0..=1.0`, every shape but the random ones starts rising from `0.0`.
+    pub fn value(&self, shape: LfoShape) -> f32 {
+        let phase = self.phase;
+        match shape {
+            LfoShape::Sine => f32::sin(2.0 * PI * phase),
+            LfoShape::Triangle => 1.0 - 4.0 * ((phase + 0.25).fract() - 0.5).abs(),
+            LfoShape::Saw => 2.0 * (phase + 0.5).fract() - 1.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            LfoShape::SampleAndHold => self.random,
+            LfoShape::SmoothRandom => {
+                let t = 0.5 - 0.5 * f32::cos(PI * phase);
+                self.last_random + (self.random - self.last_random) * t
+            }
+        }
+    }
+
+    /// The current value of `lfo`, faded in since the last retrigger.
+    pub fn faded(&self, lfo: &Lfo<f32>) -> f32 {
+        let fade = if lfo.fade_in > 0.0 {
+            (self.elapsed / lfo.fade_in).min(1.0)
+        } else {
+            1.0
+        };
+        self.value(lfo.shape()) * fade
+    }
+
+    /// Modulation amount for the destination of `lfo`.
+    pub fn output(&self, lfo: &Lfo<f32>) -> f32 {
+        self.faded(lfo) * lfo.amount
+    }
+}
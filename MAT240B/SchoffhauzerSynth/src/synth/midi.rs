@@ -0,0 +1,86 @@
+/// A channel voice message decoded from either MIDI 1.0 bytes or a MIDI 2.0 UMP packet,
+/// with all values normalized so both dialects drive the synth the same way.
+#[derive_aliases::derive(..Copy, Debug)]
+pub enum MidiMessage {
+    NoteOn { channel: u16, key: u16, velocity: f32 },
+    NoteOff { channel: u16, key: u16, velocity: f32 },
+    /// `0.0..=1.0`
+    PolyPressure { channel: u16, key: u16, pressure: f32 },
+    /// `value` is `0.0..=1.0`
+    ControlChange { channel: u16, controller: u8, value: f32 },
+    /// `0.0..=1.0`
+    ChannelPressure { channel: u16, pressure: f32 },
+    /// `-1.0..=1.0`, scaled by the pitch bend range
+    PitchBend { channel: u16, bend: f32 },
+}
+
+pub const CC_MOD_WHEEL: u8 = 1;
+pub const CC_SUSTAIN: u8 = 64;
+pub const CC_SOSTENUTO: u8 = 66;
+pub const CC_BRIGHTNESS: u8 = 74;
+pub const CC_ALL_SOUND_OFF: u8 = 120;
+pub const CC_ALL_NOTES_OFF: u8 = 123;
+
+const UMP_MIDI1_CHANNEL_VOICE: u32 = 0x2;
+const UMP_MIDI2_CHANNEL_VOICE: u32 = 0x4;
+
+fn unit_7(value: u8) -> f32 {
+    (value & 0x7f) as f32 / 127.0
+}
+
+fn unit_16(value: u32) -> f32 {
+    (value & 0xffff) as f32 / 0xffff as f32
+}
+
+fn unit_32(value: u32) -> f32 {
+    (value as f64 / u32::MAX as f64) as f32
+}
+
+impl MidiMessage {
+    pub fn from_midi1(data: [u8; 3]) -> Option<Self> {
+        let channel = (data[0] & 0x0f) as u16;
+        let [_, data1, data2] = data;
+        Some(match data[0] & 0xf0 {
+            // Note on with zero velocity is a note off with the default release velocity
+            0x90 if data2 == 0 => MidiMessage::NoteOff { channel, key: data1 as u16, velocity: unit_7(64) },
+            0x90 => MidiMessage::NoteOn { channel, key: data1 as u16, velocity: unit_7(data2) },
+            0x80 => MidiMessage::NoteOff { channel, key: data1 as u16, velocity: unit_7(data2) },
+            0xa0 => MidiMessage::PolyPressure { channel, key: data1 as u16, pressure: unit_7(data2) },
+            0xb0 => MidiMessage::ControlChange { channel, controller: data1, value: unit_7(data2) },
+            0xd0 => MidiMessage::ChannelPressure { channel, pressure: unit_7(data1) },
+            0xe0 => {
+                let bend = ((data2 as u16 & 0x7f) << 7 | (data1 as u16 & 0x7f)) as f32;
+                MidiMessage::PitchBend { channel, bend: ((bend - 8192.0) / 8192.0).max(-1.0) }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Decodes MIDI 1.0 and MIDI 2.0 channel voice packets, the UMP group is ignored.
+    pub fn from_ump(data: [u32; 4]) -> Option<Self> {
+        let [word0, word1, ..] = data;
+        let message_type = word0 >> 28;
+        if message_type == UMP_MIDI1_CHANNEL_VOICE {
+            return Self::from_midi1([(word0 >> 16) as u8, (word0 >> 8) as u8 & 0x7f, word0 as u8 & 0x7f]);
+        }
+        if message_type != UMP_MIDI2_CHANNEL_VOICE {
+            return None;
+        }
+
+        let channel = ((word0 >> 16) & 0x0f) as u16;
+        let index = ((word0 >> 8) & 0x7f) as u16;
+        Some(match (word0 >> 20) & 0x0f {
+            // Unlike MIDI 1.0, a zero velocity note on is still a note on
+            0x9 => MidiMessage::NoteOn { channel, key: index, velocity: unit_16(word1 >> 16) },
+            0x8 => MidiMessage::NoteOff { channel, key: index, velocity: unit_16(word1 >> 16) },
+            0xa => MidiMessage::PolyPressure { channel, key: index, pressure: unit_32(word1) },
+            0xb => MidiMessage::ControlChange { channel, controller: index as u8, value: unit_32(word1) },
+            0xd => MidiMessage::ChannelPressure { channel, pressure: unit_32(word1) },
+            0xe => MidiMessage::PitchBend {
+                channel,
+                bend: ((word1 as f64 - 0x8000_0000u32 as f64) / 0x8000_0000u32 as f64) as f32,
+            },
+            _ => return None,
+        })
+    }
+}
@@ -1,2 +1,15 @@
 pub mod synth;
-pub mod poly_synth;
\ No newline at end of file
+pub mod poly_synth;
+pub mod note_stack;
+pub mod note_expression;
+pub mod midi;
+pub mod pedals;
+pub mod unison;
+pub mod oscillator;
+pub mod filter;
+pub mod modulation;
+pub mod lfo;
+pub mod mod_matrix;
+
+#[cfg(test)]
+mod tests;
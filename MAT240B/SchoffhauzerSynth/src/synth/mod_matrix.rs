@@ -0,0 +1,175 @@
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::utils::atomic_param::F32Fields;
+use crate::utils::modulated::Modulated;
+use crate::utils::param_enum::ParamEnum;
+use clack_extensions::params::ParamInfo;
+use clack_plugin::prelude::ClapId;
+use core::fmt::Debug;
+use derive_more::Display;
+use repetitive::repetitive;
+
+pub const MOD_MATRIX_SLOTS: usize = 8;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum ModSource {
+    #[default]
+    Off,
+    Velocity,
+    /// Distance from middle C, reaching `-1.0` and `1.0` five octaves away
+    Key,
+    #[display("Voice LFO")]
+    VoiceLfo,
+    #[display("Global LFO")]
+    GlobalLfo,
+    #[display("Mod Env")]
+    ModEnv,
+    /// Per-note pressure, from polyphonic aftertouch or MPE
+    Pressure,
+    Brightness,
+    /// Channel pressure
+    Aftertouch,
+    #[display("Mod Wheel")]
+    ModWheel,
+}
+
+impl ParamEnum for ModSource {
+    const ALL: &'static [Self] = &[
+        ModSource::Off,
+        ModSource::Velocity,
+        ModSource::Key,
+        ModSource::VoiceLfo,
+        ModSource::GlobalLfo,
+        ModSource::ModEnv,
+        ModSource::Pressure,
+        ModSource::Brightness,
+        ModSource::Aftertouch,
+        ModSource::ModWheel,
+    ];
+}
+
+/// Current value of every [`ModSource`] for one voice, the LFOs and the key are in `-1.0..=1.0`,
+/// everything else in `0.0..=1.0`.
+#[derive_aliases::derive(..Copy, Debug, Default)]
+pub struct ModSources {
+    pub velocity: f32,
+    pub key: f32,
+    pub voice_lfo: f32,
+    pub global_lfo: f32,
+    pub mod_env: f32,
+    pub pressure: f32,
+    pub brightness: f32,
+    pub aftertouch: f32,
+    pub mod_wheel: f32,
+}
+
+impl ModSources {
+    /// [`ModSource::Off`] is `1.0`, so an unused via leaves the source untouched.
+    pub fn get(&self, source: ModSource) -> f32 {
+        match source {
+            ModSource::Off => 1.0,
+            ModSource::Velocity => self.velocity,
+            ModSource::Key => self.key,
+            ModSource::VoiceLfo => self.voice_lfo,
+            ModSource::GlobalLfo => self.global_lfo,
+            ModSource::ModEnv => self.mod_env,
+            ModSource::Pressure => self.pressure,
+            ModSource::Brightness => self.brightness,
+            ModSource::Aftertouch => self.aftertouch,
+            ModSource::ModWheel => self.mod_wheel,
+        }
+    }
+}
+
+/// One routing of the modulation matrix.
+#[derive_aliases::derive(..Copy, Debug, derive_more::Display, Default, ..SerDe)]
+#[display(bound(T: Debug))]
+#[display("{self:?}")]
+#[serde(default)]
+#[repr(C)]
+pub struct ModSlot<T> {
+    /// Stepped, see [`ModSource::from_value`]
+    pub source: T,
+    /// Stepped, scales the source by a second [`ModSource`]
+    pub via: T,
+    /// Stepped, `0.0` is off, otherwise one past the index into
+    /// [`SchoffhauzerSynthPluginParams::MOD_MATRIX_DESTINATIONS`]
+    pub destination: T,
+    /// Of the destination range
+    pub amount: T,
+}
+
+unsafe impl<T: F32Fields> F32Fields for ModSlot<T> {}
+const _: () = assert!(size_of::<ModSlot<f32>>() == 4 * size_of::<f32>());
+
+impl<T> ModSlot<T> {
+    pub fn map<R>(&self, mut f: impl FnMut(&T) -> R) -> ModSlot<R> {
+        repetitive! {
+            ModSlot {
+                @for field in ['source, 'via, 'destination, 'amount] {
+                    @field: f(&self.@field),
+                }
+            }
+        }
+    }
+}
+
+impl ModSlot<f32> {
+    pub fn source(&self) -> ModSource {
+        ModSource::from_value(self.source)
+    }
+
+    pub fn via(&self) -> ModSource {
+        ModSource::from_value(self.via)
+    }
+
+    pub fn destination(&self) -> Option<&'static ParamInfo<'static>> {
+        destination_from_value(self.destination)
+    }
+}
+
+pub fn destination_from_value(value: f32) -> Option<&'static ParamInfo<'static>> {
+    let index = (value.round().max(0.0) as usize).checked_sub(1)?;
+    SchoffhauzerSynthPluginParams::MOD_MATRIX_DESTINATIONS.get(index).copied()
+}
+
+/// What the matrix adds to each destination of one voice, on top of the host modulation.
+/// Evaluated once per voice block, so the sources are sampled at the block rate.
+#[derive_aliases::derive(..Copy, Debug, Default)]
+pub struct ModMatrixOffsets {
+    /// Destination and offset of each slot, in destination units
+    offsets: [(Option<ClapId>, f32); MOD_MATRIX_SLOTS],
+}
+
+impl ModMatrixOffsets {
+    pub fn evaluate(slots: &[ModSlot<f32>; MOD_MATRIX_SLOTS], sources: &ModSources) -> Self {
+        Self {
+            offsets: slots.map(|slot| match (slot.source(), slot.destination()) {
+                (ModSource::Off, _) | (_, None) => (None, 0.0),
+                (source, Some(info)) => {
+                    let range = (info.max_value - info.min_value) as f32;
+                    let amount = sources.get(source) * sources.get(slot.via()) * slot.amount;
+                    (Some(info.id), amount * range)
+                }
+            }),
+        }
+    }
+
+    pub fn offset(&self, info: &ParamInfo<'_>) -> f32 {
+        self.offsets
+            .iter()
+            .filter(|(id, _)| *id == Some(info.id))
+            .map(|(_, offset)| offset)
+            .sum()
+    }
+
+    /// The host modulated `value` plus the matrix offset of `info`.
+    /// Only internally modulated values are kept in the parameter range, host modulation passes through as is.
+    pub fn apply<T: Into<f32> + From<f32>>(&self, info: &ParamInfo<'_>, value: Modulated<T>) -> T {
+        let modulated = value.into_modulated::<f32>();
+        let offset = self.offset(info);
+        if offset == 0.0 {
+            return T::from(modulated);
+        }
+        T::from((modulated + offset).clamp(info.min_value as f32, info.max_value as f32))
+    }
+}
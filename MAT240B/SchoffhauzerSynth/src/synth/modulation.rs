@@ -0,0 +1,68 @@
+use crate::utils::param_enum::ParamEnum;
+use derive_more::Display;
+
+/// Voice parameters internal modulation sources can move.
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum ModDestination {
+    #[default]
+    Off,
+    #[display("Filter Cutoff")]
+    FilterCutoff,
+    Pitch,
+    #[display("HF Rolloff")]
+    HfRolloff,
+    #[display("Pulse Width")]
+    PulseWidth,
+    Volume,
+}
+
+impl ParamEnum for ModDestination {
+    const ALL: &'static [Self] = &[
+        ModDestination::Off,
+        ModDestination::FilterCutoff,
+        ModDestination::Pitch,
+        ModDestination::HfRolloff,
+        ModDestination::PulseWidth,
+        ModDestination::Volume,
+    ];
+}
+
+impl ModDestination {
+    /// Offset at full modulation, in semitones for the cutoff and pitch, in dB for the volume,
+    /// in parameter units otherwise.
+    pub fn range(self) -> f32 {
+        match self {
+            ModDestination::Off => 0.0,
+            ModDestination::FilterCutoff => 48.0,
+            ModDestination::Pitch => 24.0,
+            ModDestination::HfRolloff => 1.0,
+            ModDestination::PulseWidth => 0.5,
+            ModDestination::Volume => 24.0,
+        }
+    }
+}
+
+/// Per-sample modulation of a voice, in the units of [`ModDestination::range`].
+#[derive_aliases::derive(..Copy, Debug, Default)]
+pub struct ModOffsets {
+    pub filter_cutoff: f32,
+    pub pitch: f32,
+    pub hf_rolloff: f32,
+    pub pulse_width: f32,
+    pub volume: f32,
+}
+
+impl ModOffsets {
+    /// `amount` is in `-1.0..=1.0` of the destination range.
+    pub fn add(&mut self, destination: ModDestination, amount: f32) {
+        let offset = amount * destination.range();
+        match destination {
+            ModDestination::Off => {}
+            ModDestination::FilterCutoff => self.filter_cutoff += offset,
+            ModDestination::Pitch => self.pitch += offset,
+            ModDestination::HfRolloff => self.hf_rolloff += offset,
+            ModDestination::PulseWidth => self.pulse_width += offset,
+            ModDestination::Volume => self.volume += offset,
+        }
+    }
+}
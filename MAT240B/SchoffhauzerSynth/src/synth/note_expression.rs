@@ -0,0 +1,106 @@
+use crate::utils::lerp;
+use crate::utils::param_enum::ParamEnum;
+use clack_plugin::events::event_types::{NoteExpressionEvent, NoteExpressionType};
+use derive_more::Display;
+
+/// Where per-note pressure (polyphonic aftertouch, MPE channel pressure) is routed.
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum PressureDestination {
+    #[default]
+    Off,
+    Volume,
+    #[display("HF Rolloff")]
+    HfRolloff,
+    Pitch,
+}
+
+impl ParamEnum for PressureDestination {
+    const ALL: &'static [Self] = &[
+        PressureDestination::Off,
+        PressureDestination::Volume,
+        PressureDestination::HfRolloff,
+        PressureDestination::Pitch,
+    ];
+}
+
+impl PressureDestination {
+    /// Pitch offset in semitones at full pressure and full amount
+    pub const PITCH_RANGE: f32 = 2.0;
+}
+
+/// Latest CLAP note expression values of a voice, defaults leave the sound untouched.
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct NoteExpressions {
+    /// Linear gain, `0.0..=4.0`
+    pub volume: f32,
+    /// `0.0` is left, `0.5` center, `1.0` right
+    pub pan: f32,
+    /// In semitones
+    pub tuning: f32,
+    /// `0.0..=1.0`, `None` until the host sends one
+    pub brightness: Option<f32>,
+    /// `0.0..=1.0`, `None` until the host sends one
+    pub pressure: Option<f32>,
+}
+
+impl Default for NoteExpressions {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            pan: 0.5,
+            tuning: 0.0,
+            brightness: None,
+            pressure: None,
+        }
+    }
+}
+
+impl NoteExpressions {
+    pub fn apply(&mut self, event: &NoteExpressionEvent) {
+        let value = event.value() as f32;
+        match event.expression_type() {
+            Some(NoteExpressionType::Volume) => self.volume = value.clamp(0.0, 4.0),
+            Some(NoteExpressionType::Pan) => self.pan = value.clamp(0.0, 1.0),
+            Some(NoteExpressionType::Tuning) => self.tuning = value.clamp(-120.0, 120.0),
+            Some(NoteExpressionType::Brightness) => self.brightness = Some(value.clamp(0.0, 1.0)),
+            Some(NoteExpressionType::Pressure) => self.pressure = Some(value.clamp(0.0, 1.0)),
+            _ => {}
+        }
+    }
+
+    /// Linear gain from the volume expression and pressure,
+    /// a negative `pressure_amount` makes pressing harder quieter.
+    pub fn gain(&self, pressure_destination: PressureDestination, pressure_amount: f32) -> f32 {
+        let pressure_gain = match self.pressure {
+            Some(pressure) if pressure_destination == PressureDestination::Volume => {
+                if pressure_amount >= 0.0 {
+                    lerp(1.0..=pressure, pressure_amount)
+                } else {
+                    lerp(1.0..=1.0 - pressure, -pressure_amount)
+                }
+            }
+            _ => 1.0,
+        };
+        self.volume * pressure_gain
+    }
+
+    /// Offset added to `hf_rolloff`, brightness is centered around `0.5` like MPE timbre.
+    pub fn hf_rolloff_offset(&self, pressure_destination: PressureDestination, pressure_amount: f32) -> f32 {
+        let brightness = self.brightness.map_or(0.0, |brightness| 2.0 * (brightness - 0.5));
+        brightness + self.pressure_for(PressureDestination::HfRolloff, pressure_destination) * pressure_amount
+    }
+
+    /// Pitch offset in semitones from tuning and pressure.
+    pub fn pitch(&self, pressure_destination: PressureDestination, pressure_amount: f32) -> f32 {
+        let pressure = self.pressure_for(PressureDestination::Pitch, pressure_destination);
+        self.tuning + pressure * pressure_amount * PressureDestination::PITCH_RANGE
+    }
+
+    fn pressure_for(&self, destination: PressureDestination, pressure_destination: PressureDestination) -> f32 {
+        if destination == pressure_destination {
+            self.pressure.unwrap_or(0.0)
+        } else {
+            0.0
+        }
+    }
+}
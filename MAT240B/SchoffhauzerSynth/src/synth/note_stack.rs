@@ -0,0 +1,67 @@
+use crate::synth::poly_synth::HostNoteMatch;
+use crate::utils::param_enum::ParamEnum;
+use derive_more::Display;
+
+/// Which held note sounds in the mono play modes.
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum NotePriority {
+    /// Most recently pressed
+    #[default]
+    Last,
+    Low,
+    High,
+}
+
+impl ParamEnum for NotePriority {
+    const ALL: &'static [Self] = &[NotePriority::Last, NotePriority::Low, NotePriority::High];
+}
+
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct HeldNote {
+    pub channel: u16,
+    pub key: u16,
+    pub id: Option<u32>,
+    pub velocity: f32,
+}
+
+/// Notes currently held down, in the order they were pressed.
+pub struct NoteStack {
+    notes: Vec<HeldNote>,
+}
+
+impl NoteStack {
+    /// Every key on every channel
+    const CAPACITY: usize = 16 * 128;
+
+    pub fn new() -> Self {
+        Self {
+            notes: Vec::with_capacity(Self::CAPACITY),
+        }
+    }
+
+    /// Pushes a note on top, a retriggered key moves to the top instead of being held twice.
+    pub fn push(&mut self, note: HeldNote) {
+        self.notes
+            .retain(|held| held.channel != note.channel || held.key != note.key);
+        if self.notes.len() >= Self::CAPACITY {
+            self.notes.remove(0);
+        }
+        self.notes.push(note);
+    }
+
+    pub fn remove_matching(&mut self, mat: &HostNoteMatch) {
+        self.notes
+            .retain(|held| !mat.matches(held.channel, held.key, held.id));
+    }
+
+    pub fn select(&self, priority: NotePriority) -> Option<HeldNote> {
+        // Ties between channels go to the most recent note
+        // (`min_by_key` keeps the first minimum, `max_by_key` the last maximum)
+        match priority {
+            NotePriority::Last => self.notes.last(),
+            NotePriority::Low => self.notes.iter().rev().min_by_key(|note| note.key),
+            NotePriority::High => self.notes.iter().max_by_key(|note| note.key),
+        }
+        .copied()
+    }
+}
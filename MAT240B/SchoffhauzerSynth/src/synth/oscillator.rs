@@ -0,0 +1,104 @@
+use crate::synth::synth::Waveform;
+use crate::utils::atomic_param::F32Fields;
+use crate::utils::param_enum::ParamEnum;
+use core::fmt::Debug;
+use repetitive::repetitive;
+
+pub const OSCILLATOR_COUNT: usize = 3;
+
+/// Tuning and mix of one oscillator of a voice.
+#[derive_aliases::derive(..Copy, Debug, derive_more::Display, Default, ..SerDe)]
+#[display(bound(T: Debug))]
+#[display("{self:?}")]
+#[serde(default)]
+#[repr(C)]
+pub struct Oscillator<T> {
+    /// Stepped, see [`Waveform::from_value`]
+    pub waveform: T,
+    pub octave: T,
+    pub semitone: T,
+    /// In cents
+    pub fine: T,
+    pub level: T,
+    pub hf_rolloff: T,
+}
+
+unsafe impl<T: F32Fields> F32Fields for Oscillator<T> {}
+const _: () = assert!(size_of::<Oscillator<f32>>() == 6 * size_of::<f32>());
+
+impl<T> Oscillator<T> {
+    pub fn map<R>(&self, mut f: impl FnMut(&T) -> R) -> Oscillator<R> {
+        repetitive! {
+            Oscillator {
+                @for field in ['waveform, 'octave, 'semitone, 'fine, 'level, 'hf_rolloff] {
+                    @field: f(&self.@field),
+                }
+            }
+        }
+    }
+
+    pub fn map2<B, R>(&self, other: &Oscillator<B>, mut f: impl FnMut(&T, &B) -> R) -> Oscillator<R> {
+        repetitive! {
+            Oscillator {
+                @for field in ['waveform, 'octave, 'semitone, 'fine, 'level, 'hf_rolloff] {
+                    @field: f(&self.@field, &other.@field),
+                }
+            }
+        }
+    }
+}
+
+impl Oscillator<f32> {
+    pub fn waveform(&self) -> Waveform {
+        Waveform::from_value(self.waveform)
+    }
+
+    /// Offset from the note in semitones, octave and semitone are stepped.
+    pub fn pitch(&self) -> f32 {
+        12.0 * self.octave.round() + self.semitone.round() + self.fine / 100.0
+    }
+}
+
+/// How OSC 2 and OSC 3 follow OSC 1.
+#[derive_aliases::derive(..Copy, Debug, derive_more::Display, Default, ..SerDe)]
+#[display(bound(T: Debug))]
+#[display("{self:?}")]
+#[serde(default)]
+#[repr(C)]
+pub struct OscillatorLink<T> {
+    /// Stepped, restarts the period whenever OSC 1 does
+    pub sync: T,
+    /// Blend from the plain oscillator to its product with OSC 1
+    pub ring: T,
+}
+
+unsafe impl<T: F32Fields> F32Fields for OscillatorLink<T> {}
+const _: () = assert!(size_of::<OscillatorLink<f32>>() == 2 * size_of::<f32>());
+
+impl<T> OscillatorLink<T> {
+    pub fn map<R>(&self, mut f: impl FnMut(&T) -> R) -> OscillatorLink<R> {
+        repetitive! {
+            OscillatorLink {
+                @for field in ['sync, 'ring] {
+                    @field: f(&self.@field),
+                }
+            }
+        }
+    }
+
+    pub fn map2<B, R>(&self, other: &OscillatorLink<B>, mut f: impl FnMut(&T, &B) -> R) -> OscillatorLink<R> {
+        repetitive! {
+            OscillatorLink {
+                @for field in ['sync, 'ring] {
+                    @field: f(&self.@field, &other.@field),
+                }
+            }
+        }
+    }
+}
+
+impl OscillatorLink<f32> {
+    pub fn is_synced(&self) -> bool {
+        self.sync >= 0.5
+    }
+}
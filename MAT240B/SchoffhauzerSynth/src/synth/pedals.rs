@@ -0,0 +1,41 @@
+/// Pedal state of one MIDI channel.
+#[derive_aliases::derive(..Copy, Debug, Default)]
+pub struct ChannelPedals {
+    /// CC64, holds every note released while it is down
+    pub sustain: bool,
+    /// CC66, holds only the notes that were down when it was pressed
+    pub sostenuto: bool,
+}
+
+/// How a voice is affected by its channel's pedals.
+#[derive_aliases::derive(..Copy, Debug, Default)]
+pub struct PedalHold {
+    /// The note was released but a pedal is keeping it playing
+    pub release_pending: bool,
+    /// The note was held when sostenuto went down
+    pub sostenuto_latched: bool,
+}
+
+impl PedalHold {
+    pub fn is_held(&self, pedals: ChannelPedals) -> bool {
+        pedals.sustain || (pedals.sostenuto && self.sostenuto_latched)
+    }
+
+    /// Returns whether the note should be released now, otherwise the release is deferred.
+    pub fn release(&mut self, pedals: ChannelPedals) -> bool {
+        self.release_pending = self.is_held(pedals);
+        !self.release_pending
+    }
+
+    /// Returns whether a deferred release is due after a pedal change.
+    pub fn pedals_changed(&mut self, pedals: ChannelPedals) -> bool {
+        if !pedals.sostenuto {
+            self.sostenuto_latched = false;
+        }
+        let release = self.release_pending && !self.is_held(pedals);
+        if release {
+            self.release_pending = false;
+        }
+        release
+    }
+}
@@ -1,18 +1,122 @@
 use crate::params::SchoffhauzerSynthPluginParams;
+use crate::synth::filter::{Filter, FilterMode, FilterSlope};
+use crate::synth::midi::{
+    CC_ALL_NOTES_OFF, CC_ALL_SOUND_OFF, CC_BRIGHTNESS, CC_MOD_WHEEL, CC_SOSTENUTO, CC_SUSTAIN, MidiMessage,
+};
+use crate::synth::lfo::{FALLBACK_TEMPO, Lfo, LfoInstance, LfoRetrigger};
+use crate::synth::mod_matrix::{MOD_MATRIX_SLOTS, ModMatrixOffsets, ModSlot, ModSources};
+use crate::synth::modulation::{ModDestination, ModOffsets};
+use crate::synth::note_expression::NoteExpressions;
+use crate::synth::note_stack::{HeldNote, NoteStack};
+use crate::synth::oscillator::{OSCILLATOR_COUNT, Oscillator, OscillatorLink};
+use crate::synth::pedals::{ChannelPedals, PedalHold};
 use crate::synth::synth::Synth;
-use crate::utils::Single;
+use crate::synth::unison::Unison;
 use crate::utils::db::DB;
-use crate::utils::envelope::{ADSR, ADSRInstance};
+use crate::utils::envelope::{ADSR, ADSRInstance, ADSRPhase};
+use crate::utils::lerp;
 use crate::utils::midi_note::MidiNote;
 use crate::utils::modulated::Modulated;
-use clack_plugin::events::Match;
+use crate::utils::pan;
+use crate::utils::param_enum::ParamEnum;
+use crate::utils::smoother::Smoother;
+use crate::utils::velocity;
+use crate::utils::voice_pool::VoicePool;
+use clack_plugin::events::spaces::CoreEventSpace;
+use clack_plugin::events::{Match, Pckn, UnknownEvent};
 use clack_plugin::events::event_types::{
-    NoteChokeEvent, NoteEndEvent, NoteExpressionEvent, NoteOffEvent, NoteOnEvent, ParamModEvent,
-    ParamValueEvent,
+    NoteChokeEvent, NoteEndEvent, NoteExpressionEvent, NoteExpressionType, NoteOffEvent,
+    NoteOnEvent, ParamModEvent, ParamValueEvent, TransportEvent, TransportFlags,
 };
+use derive_more::Display;
 use repetitive::repetitive;
-use std::collections::LinkedList;
-use std::ops::Range;
+use std::cmp::Ordering;
+
+/// Fade out time of stolen voices, short enough to free them quickly but long enough not to click
+const STEAL_FADE_DURATION: f32 = 0.005;
+
+/// Glides are considered done this close to the target, in semitones
+const GLIDE_EPSILON: f32 = 0.001;
+/// Fraction of the glide left after the portamento time
+const GLIDE_RESIDUAL: f32 = 0.001;
+
+/// Smoothing time of the volume, in seconds
+const VOLUME_SMOOTHING: f32 = 0.01;
+/// Smoothing time of each oscillator's `hf_rolloff`, in seconds, longer as the timbre jumps more audibly than the level
+const HF_ROLLOFF_SMOOTHING: f32 = 0.02;
+/// Smoothing time of the filter cutoff, in seconds, short so sweeps by hand still feel immediate
+const FILTER_CUTOFF_SMOOTHING: f32 = 0.005;
+/// Smoothing time of the pan, in seconds, long as only the clicks of a jump matter, not how fast it moves
+const PAN_SMOOTHING: f32 = 0.03;
+/// Smoothing time of the pulse width, in seconds
+const PULSE_WIDTH_SMOOTHING: f32 = 0.01;
+
+type Params = SchoffhauzerSynthPluginParams;
+
+/// The parameters and the mod matrix are evaluated once per this many samples, only the LFO and mod envelope
+/// destinations follow them per sample. A matrix routed LFO steps at `sample_rate / 64`, 750 Hz at 48 kHz,
+/// which [`VoiceSmoothing`] rounds off.
+const MOD_BLOCK_SIZE: usize = 64;
+/// The [`crate::synth::mod_matrix::ModSource::Key`] source reaches `1.0` this many semitones from middle C
+const MOD_KEY_RANGE: f32 = 60.0;
+
+/// Room for the highest polyphony plus as many voices fading out after being stolen
+const VOICE_CAPACITY: usize = 2 * SchoffhauzerSynthPluginParams::MAX_POLYPHONY.max_value as usize;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum VoiceStealing {
+    #[default]
+    Oldest,
+    /// Lowest envelope level
+    Quietest,
+    /// Released voices (quietest first), then the oldest
+    #[display("Released First")]
+    ReleasedFirst,
+    /// Retriggers a voice already playing the same key, otherwise steals the oldest
+    #[display("Same Key")]
+    SameKey,
+}
+
+impl ParamEnum for VoiceStealing {
+    const ALL: &'static [Self] = &[
+        VoiceStealing::Oldest,
+        VoiceStealing::Quietest,
+        VoiceStealing::ReleasedFirst,
+        VoiceStealing::SameKey,
+    ];
+}
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum PlayMode {
+    #[default]
+    Poly,
+    /// A single voice following the held note with the highest [`crate::synth::note_stack::NotePriority`],
+    /// every note change retriggers the envelope
+    Mono,
+    /// Like [`PlayMode::Mono`], but overlapping notes keep the envelope running
+    Legato,
+}
+
+impl ParamEnum for PlayMode {
+    const ALL: &'static [Self] = &[PlayMode::Poly, PlayMode::Mono, PlayMode::Legato];
+}
+
+impl VoiceStealing {
+    /// Orders voices so that the one to steal first compares as the smallest.
+    fn compare(self, a: &Voice, b: &Voice) -> Ordering {
+        let oldest = a.age.cmp(&b.age);
+        let quietest = a.level().total_cmp(&b.level());
+        match self {
+            VoiceStealing::Oldest | VoiceStealing::SameKey => oldest,
+            VoiceStealing::Quietest => quietest.then(oldest),
+            VoiceStealing::ReleasedFirst => b
+                .is_released()
+                .cmp(&a.is_released())
+                .then_with(|| if a.is_released() { quietest } else { Ordering::Equal })
+                .then(oldest),
+        }
+    }
+}
 
 pub struct HostNoteMatch {
     channel: Match<u16>,
@@ -20,6 +124,18 @@ pub struct HostNoteMatch {
     id: Match<u32>,
 }
 
+impl HostNoteMatch {
+    /// Notes without an id are only matched by a wildcard id.
+    pub fn matches(&self, channel: u16, note: u16, id: Option<u32>) -> bool {
+        let id_matches = if let Some(id) = id {
+            self.id.matches(id)
+        } else {
+            self.id.is_all()
+        };
+        id_matches && self.channel.matches(channel) && self.note.matches(note)
+    }
+}
+
 repetitive! {
     @for event in [
         'NoteOnEvent,
@@ -53,71 +169,511 @@ enum NoteIdent {
     _Other(u32),
 }
 
+/// Voice settings resolved once per block, before per-sample modulation.
+struct VoiceBlock {
+    oscillators: [Oscillator<f32>; OSCILLATOR_COUNT],
+    sub_octave: i32,
+    /// In semitones, from note expressions
+    pitch: f32,
+    hf_rolloff_offset: f32,
+    pulse_width: f32,
+    pan: f32,
+    unison_spread: f32,
+    filter_mode: FilterMode,
+    filter_slope: FilterSlope,
+    /// As a MIDI note number, including key tracking
+    filter_cutoff: f32,
+    filter_resonance: f32,
+}
+
+/// Per sample smoothing of the continuous parameters that otherwise only change once per block.
+/// The LFO and mod envelope destinations are already evaluated per sample, so only the block values,
+/// including the mod matrix offsets, go through here.
+struct VoiceSmoothing {
+    /// Linear gain
+    volume: Smoother,
+    hf_rolloffs: [Smoother; OSCILLATOR_COUNT],
+    /// As a MIDI note number, so sweeps move evenly in pitch
+    filter_cutoff: Smoother,
+    pan: Smoother,
+    pulse_width: Smoother,
+}
+
+impl VoiceSmoothing {
+    fn new(sample_rate: f32) -> Self {
+        Self {
+            volume: Smoother::new(VOLUME_SMOOTHING, sample_rate),
+            hf_rolloffs: [Smoother::new(HF_ROLLOFF_SMOOTHING, sample_rate); OSCILLATOR_COUNT],
+            filter_cutoff: Smoother::new(FILTER_CUTOFF_SMOOTHING, sample_rate),
+            pan: Smoother::new(PAN_SMOOTHING, sample_rate),
+            pulse_width: Smoother::new(PULSE_WIDTH_SMOOTHING, sample_rate),
+        }
+    }
+}
+
+/// Modulation shared by all voices, as of the start of a block.
+struct SharedModulation {
+    /// In beats per minute
+    tempo: f64,
+    global_lfo: Lfo<f32>,
+    /// Each voice runs its own copy through the block, so they all stay in step
+    global_lfo_instance: LfoInstance,
+    mod_matrix: [ModSlot<f32>; MOD_MATRIX_SLOTS],
+    /// Per channel, `0.0..=1.0`
+    aftertouch: [f32; 16],
+    /// Per channel, `0.0..=1.0`
+    mod_wheels: [f32; 16],
+}
+
+/// Everything a new voice takes from the synth rather than from its note.
+struct VoiceStart {
+    sample_rate: f32,
+    /// See [`Voice::age`]
+    age: u64,
+    /// In semitones, of the note's channel
+    pitch_bend: f32,
+    lfo_phase: f32,
+}
+
 struct Voice {
     ident: NoteIdent,
-    synth: Synth,
+    /// Order of note on, lower is older
+    age: u64,
+    pedal_hold: PedalHold,
+    /// Remaining gain while fading out after being stolen
+    steal_fade: Option<f32>,
+    sample_rate: f32,
+    unisons: [Unison; OSCILLATOR_COUNT],
+    sub: Synth,
+    /// `(left, right)` gains of the sub-oscillator, following the smoothed pan
+    sub_gains: (f32, f32),
+    unison_detune: Modulated<Option<f32>>,
+    unison_spread: Modulated<Option<f32>>,
+    pulse_width: Modulated<Option<f32>>,
+    base_freq: f32,
+    /// In semitones
+    pitch_bend: f32,
+    /// Remaining portamento offset in semitones, decays towards `0.0`
+    glide: f32,
+    portamento: Modulated<Option<f32>>,
     volume: Modulated<Option<DB<f32>>>,
     adsr: ADSR<Modulated<Option<f32>>>,
     adsr_instance: ADSRInstance,
-    hf_rolloff: Modulated<Option<f32>>,
+    mod_env: ADSR<Modulated<Option<f32>>>,
+    mod_env_instance: ADSRInstance,
+    mod_env_amount: Modulated<Option<f32>>,
+    lfo: Lfo<Modulated<Option<f32>>>,
+    lfo_instance: LfoInstance,
+    oscillators: [Oscillator<Modulated<Option<f32>>>; OSCILLATOR_COUNT],
+    oscillator_links: [OscillatorLink<Modulated<Option<f32>>>; OSCILLATOR_COUNT - 1],
+    sub_level: Modulated<Option<f32>>,
+    filter: Filter,
+    filter_cutoff: Modulated<Option<f32>>,
+    filter_resonance: Modulated<Option<f32>>,
+    velocity: f32,
+    velocity_amount: Modulated<Option<f32>>,
+    velocity_hf_rolloff: Modulated<Option<f32>>,
+    velocity_release: Modulated<Option<f32>>,
+    /// Set once the note is released
+    release_velocity: Option<f32>,
+    expressions: NoteExpressions,
+    pressure_amount: Modulated<Option<f32>>,
+    pan: Modulated<Option<f32>>,
+    smoothing: VoiceSmoothing,
 }
 
 impl Voice {
-    fn new_host(
-        _params: &SchoffhauzerSynthPluginParams,
-        sample_rate: f32,
-        channel: u16,
-        note: u16,
-        id: Option<u32>,
-        _velocity: f32,
-    ) -> Self {
-        let note = MidiNote(note);
+    fn new_host(_params: &SchoffhauzerSynthPluginParams, held: &HeldNote, start: VoiceStart) -> Self {
+        let HeldNote { channel, key, id, velocity } = *held;
+        let VoiceStart { sample_rate, age, pitch_bend, lfo_phase } = start;
+        let note = MidiNote(key);
         Self {
             ident: NoteIdent::Host(NoteIdentHost { channel, note, id }),
-            synth: Synth::new(sample_rate, note.freq()),
+            age,
+            pedal_hold: PedalHold::default(),
+            steal_fade: None,
+            sample_rate,
+            unisons: std::array::from_fn(|i| {
+                Unison::new(sample_rate, note.freq(), age as u32 * OSCILLATOR_COUNT as u32 + i as u32)
+            }),
+            sub: Synth::new(sample_rate, note.freq() / 2.0),
+            sub_gains: (1.0, 1.0),
+            unison_detune: Modulated::new(None, None),
+            unison_spread: Modulated::new(None, None),
+            pulse_width: Modulated::new(None, None),
+            base_freq: note.freq(),
+            pitch_bend,
+            glide: 0.0,
+            portamento: Modulated::new(None, None),
             volume: Modulated::new(None, None),
             adsr: ADSR::default(),
             adsr_instance: ADSRInstance::new(ADSR::default()),
-            hf_rolloff: Modulated::new(None, None),
+            mod_env: ADSR::default(),
+            mod_env_instance: ADSRInstance::new(ADSR::default()),
+            mod_env_amount: Modulated::new(None, None),
+            lfo: Lfo::default(),
+            lfo_instance: LfoInstance::new(age as u32, lfo_phase),
+            oscillators: [Oscillator::default(); OSCILLATOR_COUNT],
+            oscillator_links: [OscillatorLink::default(); OSCILLATOR_COUNT - 1],
+            sub_level: Modulated::new(None, None),
+            filter: Filter::new(sample_rate),
+            filter_cutoff: Modulated::new(None, None),
+            filter_resonance: Modulated::new(None, None),
+            velocity,
+            velocity_amount: Modulated::new(None, None),
+            velocity_hf_rolloff: Modulated::new(None, None),
+            velocity_release: Modulated::new(None, None),
+            release_velocity: None,
+            expressions: NoteExpressions::default(),
+            pressure_amount: Modulated::new(None, None),
+            pan: Modulated::new(None, None),
+            smoothing: VoiceSmoothing::new(sample_rate),
         }
     }
 
     fn match_host(&self, mat: &HostNoteMatch) -> bool {
         if let NoteIdent::Host(ident) = &self.ident {
-            let id_matches = if let Some(id) = ident.id {
-                mat.id.matches(id)
-            } else {
-                mat.id.is_all()
-            };
-            id_matches && mat.channel.matches(ident.channel) && mat.note.matches(ident.note.midi())
+            mat.matches(ident.channel, ident.note.midi(), ident.id)
         } else {
             false
         }
     }
 
-    fn off(&mut self, _velocity: f32) {
+    fn is_host_note(&self, note: &HeldNote) -> bool {
+        self.is_host_key(note.channel, note.key)
+            && matches!(&self.ident, NoteIdent::Host(ident) if ident.id == note.id)
+    }
+
+    /// Semitones from `freq` to the pitch currently playing, ignoring pitch bend.
+    fn glide_to(&self, freq: f32) -> f32 {
+        self.glide + 12.0 * f32::log2(self.base_freq / freq)
+    }
+
+    /// Moves this voice to another note without retriggering it, gliding from the current pitch.
+    fn retarget(&mut self, note: &HeldNote, pitch_bend: f32) {
+        let midi_note = MidiNote(note.key);
+        self.glide = self.glide_to(midi_note.freq());
+        self.base_freq = midi_note.freq();
+        self.pitch_bend = pitch_bend;
+        self.ident = NoteIdent::Host(NoteIdentHost {
+            channel: note.channel,
+            note: midi_note,
+            id: note.id,
+        });
+    }
+
+    fn channel(&self) -> Option<u16> {
+        match &self.ident {
+            NoteIdent::Host(ident) => Some(ident.channel),
+            NoteIdent::_Other(_) => None,
+        }
+    }
+
+    fn is_host_key(&self, channel: u16, key: u16) -> bool {
+        match &self.ident {
+            NoteIdent::Host(ident) => ident.channel == channel && ident.note.midi() == key,
+            NoteIdent::_Other(_) => false,
+        }
+    }
+
+    fn level(&self) -> f32 {
+        self.adsr_instance.current_level() * self.steal_fade.unwrap_or(1.0)
+    }
+
+    fn is_released(&self) -> bool {
+        self.adsr_instance.phase() == Some(ADSRPhase::Release)
+    }
+
+    fn is_stolen(&self) -> bool {
+        self.steal_fade.is_some()
+    }
+
+    fn steal(&mut self) {
+        self.steal_fade.get_or_insert(1.0);
+    }
+
+    /// `velocity` shortens the amplitude release, see [`velocity::release_scale`].
+    fn off(&mut self, velocity: f32) {
+        self.release_velocity = Some(velocity);
         self.adsr_instance.off();
+        self.mod_env_instance.off();
+    }
+
+    fn pedals(&self, pedals: &[ChannelPedals; 16]) -> ChannelPedals {
+        self.channel()
+            .and_then(|channel| pedals.get(channel as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Releases the voice, unless a pedal of its channel holds it.
+    fn release(&mut self, velocity: f32, pedals: &[ChannelPedals; 16]) {
+        if self.pedal_hold.release(self.pedals(pedals)) {
+            self.off(velocity);
+        }
     }
 
     fn choke(&mut self) {
         self.adsr_instance.force_end();
     }
 
-    fn synth_add_to(&mut self, buffer: &mut [f32], params: &SchoffhauzerSynthPluginParams) -> bool {
-        let volume = self.volume.unwrap_or(params.get_volume()).modulated();
+    /// `pitch_offset` is in semitones, on top of pitch bend and glide.
+    /// The sub-oscillator follows OSC 1 `sub_octave` octaves lower.
+    fn update_freq(
+        &mut self,
+        pitch_offset: f32,
+        oscillators: &[Oscillator<f32>; OSCILLATOR_COUNT],
+        sub_octave: i32,
+    ) {
+        let semitones = self.pitch_bend + self.glide + pitch_offset;
+        for (unison, oscillator) in self.unisons.iter_mut().zip(oscillators) {
+            unison.set_freq(self.base_freq * f32::powf(2.0, (semitones + oscillator.pitch()) / 12.0));
+        }
+        let sub_semitones = semitones + oscillators[0].pitch() + 12.0 * sub_octave as f32;
+        self.sub.freq = self.base_freq * f32::powf(2.0, sub_semitones / 12.0);
+    }
+
+    /// Moves the smoothed block settings one sample towards the block values, then applies them,
+    /// moved by `offsets`, to the oscillators and the filter. Unless `refresh` is set, settings that
+    /// have settled are left as they are, as `offsets` are then the same as when they were applied.
+    fn apply_smoothed(&mut self, block: &VoiceBlock, offsets: &ModOffsets, refresh: bool) {
+        let oscillators = self.unisons.iter_mut().zip(&block.oscillators).zip(&mut self.smoothing.hf_rolloffs);
+        for ((unison, oscillator), smoother) in oscillators {
+            let hf_rolloff = smoother.next(oscillator.hf_rolloff + block.hf_rolloff_offset) + offsets.hf_rolloff;
+            unison.set_hf_rolloff(hf_rolloff.clamp(0.0, 1.0));
+        }
+        if refresh || !self.smoothing.pulse_width.is_settled(block.pulse_width) {
+            let pulse_width = self.smoothing.pulse_width.next(block.pulse_width) + offsets.pulse_width;
+            for unison in &mut self.unisons {
+                unison.set_pulse_width(pulse_width);
+            }
+        }
+        if refresh || !self.smoothing.pan.is_settled(block.pan) {
+            let pan = self.smoothing.pan.next(block.pan);
+            for unison in &mut self.unisons {
+                unison.set_pan(pan, block.unison_spread);
+            }
+            self.sub_gains = pan::balance(pan);
+        }
+        if refresh || !self.smoothing.filter_cutoff.is_settled(block.filter_cutoff) {
+            let filter_cutoff = self.smoothing.filter_cutoff.next(block.filter_cutoff) + offsets.filter_cutoff;
+            self.filter.configure(
+                block.filter_mode,
+                block.filter_slope,
+                MidiNote(filter_cutoff).freq(),
+                block.filter_resonance,
+            );
+        }
+    }
+
+    fn synth_add_to(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        params: &SchoffhauzerSynthPluginParams,
+        shared: &SharedModulation,
+    ) -> bool {
+        // Sources are read before the matrix applies, so the voice LFO fades in at its unmodulated speed
+        let lfo = self.lfo.map2(&params.get_voice_lfo(), |a, b| a.unwrap_or(*b));
+        let channel_value = |values: &[f32; 16]| {
+            self.channel().and_then(|channel| values.get(channel as usize)).copied().unwrap_or_default()
+        };
+        let sources = ModSources {
+            velocity: self.velocity,
+            key: 12.0 * f32::log2(self.base_freq / MidiNote(60u16).freq()) / MOD_KEY_RANGE,
+            voice_lfo: self.lfo_instance.faded(&lfo.map(|it| it.modulated())),
+            global_lfo: shared.global_lfo_instance.faded(&shared.global_lfo),
+            mod_env: self.mod_env_instance.current_level(),
+            pressure: self.expressions.pressure.unwrap_or(0.0),
+            brightness: self.expressions.brightness.unwrap_or(0.5),
+            aftertouch: channel_value(&shared.aftertouch),
+            mod_wheel: channel_value(&shared.mod_wheels),
+        };
+        // Held for the whole block, see `MOD_BLOCK_SIZE`
+        let matrix = ModMatrixOffsets::evaluate(&shared.mod_matrix, &sources);
+
+        let pressure_destination = params.get_pressure_destination();
+        let pressure_amount = matrix.apply(
+            Params::PRESSURE_AMOUNT,
+            self.pressure_amount.unwrap_or(params.get_pressure_amount()),
+        );
+        let expression_gain = self.expressions.gain(pressure_destination, pressure_amount);
+
+        let volume = matrix.apply(Params::VOLUME, self.volume.unwrap_or(params.get_volume()));
+        let velocity_amount = matrix.apply(
+            Params::VELOCITY_AMOUNT,
+            self.velocity_amount.unwrap_or(params.get_velocity_amount()),
+        );
+        let velocity_gain = lerp(1.0..=params.get_velocity_curve().gain(self.velocity), velocity_amount);
         
         let adsr = self.adsr.map2(&params.get_adsr(), |a, b| a.unwrap_or(*b));
-        let adsr = adsr.map(|it| it.modulated());
+        let mut adsr = Params::ADSR.map2(&adsr, |info, it| matrix.apply(info, *it));
+        if let Some(release_velocity) = self.release_velocity {
+            let velocity_release = matrix.apply(
+                Params::VELOCITY_RELEASE,
+                self.velocity_release.unwrap_or(params.get_velocity_release()),
+            );
+            adsr.release_duration *=
+                velocity::release_scale(params.get_velocity_curve(), velocity_release, release_velocity);
+        }
         self.adsr_instance.adsr = adsr;
+
+        let mod_env = self.mod_env.map2(&params.get_mod_env(), |a, b| a.unwrap_or(*b));
+        self.mod_env_instance.adsr = Params::MOD_ENV.map2(&mod_env, |info, it| matrix.apply(info, *it));
+        let mod_env_destination = params.get_mod_env_destination();
+        let mod_env_amount = matrix.apply(
+            Params::MOD_ENV_AMOUNT,
+            self.mod_env_amount.unwrap_or(params.get_mod_env_amount()),
+        );
+
+        let lfo = Params::VOICE_LFO.map2(&lfo, |info, it| matrix.apply(info, *it));
+        let lfo_destination = lfo.destination();
+        let lfo_step = lfo.frequency(shared.tempo) / self.sample_rate;
+        // All voices share the global LFO's phase, so only how it fades in and how far it moves this voice can differ
+        let global_lfo = Lfo {
+            fade_in: matrix.apply(Params::GLOBAL_LFO.fade_in, Modulated::new(shared.global_lfo.fade_in, 0.0)),
+            amount: matrix.apply(Params::GLOBAL_LFO.amount, Modulated::new(shared.global_lfo.amount, 0.0)),
+            ..shared.global_lfo
+        };
+        let mut global_lfo_instance = shared.global_lfo_instance;
+        let global_lfo_destination = global_lfo.destination();
+        let global_lfo_step = global_lfo.frequency(shared.tempo) / self.sample_rate;
+        let modulated = [mod_env_destination, lfo_destination, global_lfo_destination]
+            .iter()
+            .any(|&destination| destination != ModDestination::Off);
         
-        self.synth.hf_rolloff = self.hf_rolloff.unwrap_or(params.get_hf_rolloff()).modulated();
-
-        for sample_ref in buffer {
-            let mut sample = self.synth.synth();
-            sample *= volume.linear();
-            self.adsr_instance.advance(1.0 / self.synth.sample_rate);
-            sample *= self.adsr_instance.current_level();
-            *sample_ref += sample;
+        let oscillators = params.get_oscillators();
+        let oscillators: [Oscillator<f32>; OSCILLATOR_COUNT] = std::array::from_fn(|i| {
+            let oscillator = self.oscillators[i].map2(&oscillators[i], |a, b| a.unwrap_or(*b));
+            Params::OSCILLATORS[i].map2(&oscillator, |info, it| matrix.apply(info, *it))
+        });
+        let oscillator_links = params.get_oscillator_links();
+        let oscillator_links: [OscillatorLink<f32>; OSCILLATOR_COUNT - 1] = std::array::from_fn(|i| {
+            let link = self.oscillator_links[i].map2(&oscillator_links[i], |a, b| a.unwrap_or(*b));
+            Params::OSCILLATOR_LINKS[i].map2(&link, |info, it| matrix.apply(info, *it))
+        });
+        // OSC 1 keeps running while silent if an audible oscillator follows it
+        let master_needed = oscillators[0].level != 0.0
+            || oscillators[1..].iter().zip(&oscillator_links).any(|(oscillator, link)| {
+                oscillator.level != 0.0 && (link.is_synced() || link.ring != 0.0)
+            });
+        let sub_level = matrix.apply(Params::SUB_LEVEL, self.sub_level.unwrap_or(params.get_sub_level()));
+
+        // Full velocity leaves `hf_rolloff` untouched, softer notes move it by up to the velocity amount
+        let velocity_hf_rolloff = matrix.apply(
+            Params::VELOCITY_HF_ROLLOFF,
+            self.velocity_hf_rolloff.unwrap_or(params.get_velocity_hf_rolloff()),
+        );
+        let hf_rolloff_offset = velocity::hf_rolloff_offset(velocity_hf_rolloff, self.velocity)
+            + self.expressions.hf_rolloff_offset(pressure_destination, pressure_amount);
+
+        // The PAN note expression is centered around 0.5
+        let pan = matrix.apply(Params::PAN, self.pan.unwrap_or(params.get_pan())) + 2.0 * (self.expressions.pan - 0.5);
+        let unison_detune = matrix.apply(Params::UNISON_DETUNE, self.unison_detune.unwrap_or(params.get_unison_detune()));
+        let unison_spread = matrix.apply(Params::UNISON_SPREAD, self.unison_spread.unwrap_or(params.get_unison_spread()));
+        for (unison, oscillator) in self.unisons.iter_mut().zip(&oscillators) {
+            unison.configure(params.get_unison_voices(), unison_detune);
+            unison.set_waveform(oscillator.waveform());
+        }
+        // Without feedback the sub stays close to a sine
+        self.sub.hf_rolloff = 0.0;
+        // Applied per voice so the matrix can widen each voice on its own
+        let width = matrix.apply(Params::WIDTH, params.get_width());
+
+        // Key tracking pivots around middle C
+        let filter_key_tracking = matrix.apply(Params::FILTER_KEY_TRACKING, params.get_filter_key_tracking());
+        let block = VoiceBlock {
+            oscillators,
+            sub_octave: params.get_sub_octave(),
+            pitch: self.expressions.pitch(pressure_destination, pressure_amount),
+            hf_rolloff_offset,
+            pulse_width: matrix.apply(Params::PULSE_WIDTH, self.pulse_width.unwrap_or(params.get_pulse_width())),
+            pan,
+            unison_spread,
+            filter_mode: params.get_filter_mode(),
+            filter_slope: params.get_filter_slope(),
+            filter_cutoff: matrix.apply(Params::FILTER_CUTOFF, self.filter_cutoff.unwrap_or(params.get_filter_cutoff()))
+                + filter_key_tracking * 12.0 * f32::log2(self.base_freq / MidiNote(60u16).freq()),
+            filter_resonance: matrix.apply(
+                Params::FILTER_RESONANCE,
+                self.filter_resonance.unwrap_or(params.get_filter_resonance()),
+            ),
+        };
+
+        let portamento = matrix.apply(Params::PORTAMENTO, self.portamento.unwrap_or(params.get_portamento()));
+        let glide_coefficient = if portamento > 0.0 {
+            GLIDE_RESIDUAL.powf(1.0 / (portamento * self.sample_rate))
+        } else {
+            self.glide = 0.0;
+            0.0
+        };
+        let mut offsets = ModOffsets::default();
+        self.update_freq(block.pitch, &block.oscillators, block.sub_octave);
+
+        let steal_fade_step = 1.0 / (STEAL_FADE_DURATION * self.sample_rate);
+
+        for (i, (left_ref, right_ref)) in left.iter_mut().zip(right).enumerate() {
+            let gliding = self.glide != 0.0;
+            if gliding {
+                self.glide *= glide_coefficient;
+                if self.glide.abs() < GLIDE_EPSILON {
+                    self.glide = 0.0;
+                }
+            }
+            // Modulation sources keep running while unused, so switching a destination mid-note follows them
+            self.mod_env_instance.advance(1.0 / self.sample_rate);
+            self.lfo_instance.advance(lfo_step, 1.0 / self.sample_rate);
+            global_lfo_instance.advance(global_lfo_step, 1.0 / self.sample_rate);
+            if modulated {
+                offsets = ModOffsets::default();
+                offsets.add(mod_env_destination, self.mod_env_instance.current_level() * mod_env_amount);
+                offsets.add(lfo_destination, self.lfo_instance.output(&lfo));
+                offsets.add(global_lfo_destination, global_lfo_instance.output(&global_lfo));
+            }
+            if modulated || gliding {
+                self.update_freq(block.pitch + offsets.pitch, &block.oscillators, block.sub_octave);
+            }
+            // The block values may have changed since the last block, and the offsets since the last sample
+            self.apply_smoothed(&block, &offsets, modulated || i == 0);
+
+            let (mut left, mut right) = (0.0, 0.0);
+            let (master, slaves) = self.unisons.split_first_mut().unwrap();
+            let (master_left, master_right) = if master_needed { master.synth() } else { (0.0, 0.0) };
+            left += master_left * block.oscillators[0].level;
+            right += master_right * block.oscillators[0].level;
+            let master_mono = 0.5 * (master_left + master_right);
+            for ((unison, oscillator), link) in slaves.iter_mut().zip(&block.oscillators[1..]).zip(&oscillator_links) {
+                // Silent oscillators cost nothing
+                if oscillator.level != 0.0 {
+                    if link.is_synced() {
+                        unison.sync_to(master);
+                    }
+                    let (unison_left, unison_right) = unison.synth();
+                    let ring = lerp(1.0..=master_mono, link.ring);
+                    left += unison_left * ring * oscillator.level;
+                    right += unison_right * ring * oscillator.level;
+                }
+            }
+            if sub_level != 0.0 {
+                let sub = self.sub.synth() * sub_level;
+                left += sub * self.sub_gains.0;
+                right += sub * self.sub_gains.1;
+            }
+            let (left, right) = self.filter.process(left, right);
+            let mut gain = self.smoothing.volume.next(volume.linear()) * velocity_gain * expression_gain
+                * DB(offsets.volume).linear();
+            self.adsr_instance.advance(1.0 / self.sample_rate);
+            gain *= self.adsr_instance.current_level();
+            if let Some(steal_fade) = &mut self.steal_fade {
+                *steal_fade -= steal_fade_step;
+                if *steal_fade <= 0.0 {
+                    return false;
+                }
+                gain *= *steal_fade;
+            }
+            let (left, right) = pan::width(left * gain, right * gain, width);
+            *left_ref += left;
+            *right_ref += right;
             if self.adsr_instance.ended() {
                 return false;
             }
@@ -129,7 +685,23 @@ impl Voice {
 pub struct PolySynth {
     sample_rate: f32,
 
-    voices: LinkedList<Voice>,
+    voices: VoicePool<Voice>,
+    next_voice_age: u64,
+    held_notes: NoteStack,
+    /// Per-channel pitch bend in semitones, also applied to notes started after the bend
+    pitch_bends: [f32; 16],
+    pedals: [ChannelPedals; 16],
+    /// In beats per minute, `None` until the host reports it
+    tempo: Option<f64>,
+    /// In beats, while the host transport is playing
+    song_position: Option<f64>,
+    /// Since activation, drives free running LFOs without a song position
+    seconds: f64,
+    global_lfo: LfoInstance,
+    /// Per channel, `0.0..=1.0`
+    aftertouch: [f32; 16],
+    /// Per channel, `0.0..=1.0`
+    mod_wheels: [f32; 16],
 }
 
 impl PolySynth {
@@ -137,17 +709,173 @@ impl PolySynth {
         Self {
             sample_rate,
 
-            voices: LinkedList::new(),
+            voices: VoicePool::with_capacity(VOICE_CAPACITY),
+            next_voice_age: 0,
+            held_notes: NoteStack::new(),
+            pitch_bends: [0.0; 16],
+            pedals: [ChannelPedals::default(); 16],
+            tempo: None,
+            song_position: None,
+            seconds: 0.0,
+            global_lfo: LfoInstance::new(0, 0.0),
+            aftertouch: [0.0; 16],
+            mod_wheels: [0.0; 16],
         }
     }
 
-    pub fn synth(&mut self, buffer: &mut [f32], params: &SchoffhauzerSynthPluginParams) {
-        let mut cursor = self.voices.cursor_front_mut();
-        while let Some(voice) = cursor.current() {
-            if voice.synth_add_to(buffer, params) {
-                cursor.move_next();
-            } else {
-                cursor.remove_current();
+    fn tempo(&self) -> f64 {
+        self.tempo.unwrap_or(FALLBACK_TEMPO)
+    }
+
+    /// Adds the voices to `left` and `right`, which must have the same length.
+    pub fn synth(&mut self, left: &mut [f32], right: &mut [f32], params: &SchoffhauzerSynthPluginParams) {
+        debug_assert_eq!(left.len(), right.len());
+        for (left, right) in left.chunks_mut(MOD_BLOCK_SIZE).zip(right.chunks_mut(MOD_BLOCK_SIZE)) {
+            self.synth_block(left, right, params);
+        }
+    }
+
+    fn synth_block(&mut self, left: &mut [f32], right: &mut [f32], params: &SchoffhauzerSynthPluginParams) {
+        let tempo = self.tempo();
+        let global_lfo = params.get_global_lfo().map(|it| it.modulated());
+        // A free running synced LFO follows the host wherever it jumps to
+        if global_lfo.retrigger() == LfoRetrigger::Free && global_lfo.is_synced() && self.song_position.is_some() {
+            self.global_lfo.sync(global_lfo.free_phase(self.song_position, self.seconds, tempo));
+        }
+        let shared = SharedModulation {
+            tempo,
+            global_lfo,
+            global_lfo_instance: self.global_lfo,
+            mod_matrix: params.get_mod_matrix().map(|slot| slot.map(|it| it.modulated())),
+            aftertouch: self.aftertouch,
+            mod_wheels: self.mod_wheels,
+        };
+        self.voices
+            .retain_mut(|voice| voice.synth_add_to(left, right, params, &shared));
+
+        let global_lfo_step = global_lfo.frequency(tempo) / self.sample_rate;
+        for _ in 0..left.len() {
+            self.global_lfo.advance(global_lfo_step, 1.0 / self.sample_rate);
+        }
+        let seconds = left.len() as f64 / self.sample_rate as f64;
+        self.seconds += seconds;
+        if let Some(song_position) = &mut self.song_position {
+            *song_position += seconds * tempo / 60.0;
+        }
+    }
+
+    /// Follows the tempo and song position of the host, for synced LFOs.
+    pub fn handle_transport_event(&mut self, event: &TransportEvent) {
+        self.tempo = event.flags.contains(TransportFlags::HAS_TEMPO).then_some(event.tempo);
+        let playing = event.flags.contains(TransportFlags::IS_PLAYING)
+            && event.flags.contains(TransportFlags::HAS_BEATS_TIMELINE);
+        self.song_position = playing.then(|| event.song_pos_beats.to_float());
+    }
+
+    pub fn handle_event(&mut self, event: &UnknownEvent, params: &SchoffhauzerSynthPluginParams) {
+        match event.as_core_event() {
+            Some(CoreEventSpace::NoteOn(event)) => self.handle_note_on_event(event, params),
+            Some(CoreEventSpace::NoteOff(event)) => self.handle_note_off_event(event, params),
+            Some(CoreEventSpace::NoteChoke(event)) => self.handle_note_choke_event(event),
+            Some(CoreEventSpace::ParamValue(event)) => {
+                if event.pckn().matches_all() {
+                    params.handle_param_value_event(event);
+                } else {
+                    self.handle_param_value_event(event);
+                }
+            }
+            Some(CoreEventSpace::ParamMod(event)) => {
+                if event.pckn().matches_all() {
+                    params.handle_param_mod_event(event);
+                } else {
+                    self.handle_param_mod_event(event);
+                }
+            }
+            Some(CoreEventSpace::NoteExpression(event)) => self.handle_note_expression_event(event),
+            Some(CoreEventSpace::Transport(event)) => self.handle_transport_event(event),
+            Some(CoreEventSpace::Midi(event)) if event.port_index() == 0 => {
+                if let Some(message) = MidiMessage::from_midi1(event.data()) {
+                    self.handle_midi_message(message, params);
+                }
+            }
+            Some(CoreEventSpace::Midi2(event)) if event.port_index() == 0 => {
+                if let Some(message) = MidiMessage::from_ump(event.data()) {
+                    self.handle_midi_message(message, params);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Translates raw MIDI into the same operations as the equivalent CLAP events.
+    pub fn handle_midi_message(&mut self, message: MidiMessage, params: &SchoffhauzerSynthPluginParams) {
+        let pckn = |channel: u16, key: Option<u16>| {
+            Pckn::new(0u16, channel, key.map_or(Match::All, Match::Specific), Match::All)
+        };
+        let expression = |channel: u16, key: Option<u16>, expression, value: f32| {
+            NoteExpressionEvent::new(0, pckn(channel, key), expression, value as f64)
+        };
+
+        match message {
+            MidiMessage::NoteOn { channel, key, velocity } => self.handle_note_on_event(
+                &NoteOnEvent::new(0, pckn(channel, Some(key)), velocity as f64),
+                params,
+            ),
+            MidiMessage::NoteOff { channel, key, velocity } => self.handle_note_off_event(
+                &NoteOffEvent::new(0, pckn(channel, Some(key)), velocity as f64),
+                params,
+            ),
+            MidiMessage::PolyPressure { channel, key, pressure } => self.handle_note_expression_event(
+                &expression(channel, Some(key), NoteExpressionType::Pressure, pressure),
+            ),
+            MidiMessage::ChannelPressure { channel, pressure } => {
+                if let Some(aftertouch) = self.aftertouch.get_mut(channel as usize) {
+                    *aftertouch = pressure;
+                }
+                self.handle_note_expression_event(&expression(channel, None, NoteExpressionType::Pressure, pressure))
+            }
+            MidiMessage::PitchBend { channel, bend } => {
+                self.set_pitch_bend(channel, bend * params.get_pitch_bend_range().value)
+            }
+            MidiMessage::ControlChange { channel, controller, value } => match controller {
+                CC_MOD_WHEEL => {
+                    if let Some(mod_wheel) = self.mod_wheels.get_mut(channel as usize) {
+                        *mod_wheel = value;
+                    }
+                }
+                CC_SUSTAIN => self.set_pedals(channel, |pedals| pedals.sustain = value >= 0.5),
+                CC_SOSTENUTO => self.set_pedals(channel, |pedals| pedals.sostenuto = value >= 0.5),
+                CC_BRIGHTNESS => self.handle_note_expression_event(
+                    &expression(channel, None, NoteExpressionType::Brightness, value),
+                ),
+                CC_ALL_SOUND_OFF => {
+                    self.handle_note_choke_event(&NoteChokeEvent::new(0, pckn(channel, None)))
+                }
+                CC_ALL_NOTES_OFF => self.handle_note_off_event(
+                    &NoteOffEvent::new(0, pckn(channel, None), 0.5),
+                    params,
+                ),
+                _ => {}
+            },
+        }
+    }
+
+    /// Updates the pedals of a channel, releasing the notes they no longer hold.
+    pub fn set_pedals(&mut self, channel: u16, f: impl FnOnce(&mut ChannelPedals)) {
+        let Some(pedals) = self.pedals.get_mut(channel as usize) else {
+            return;
+        };
+        let was_sostenuto = pedals.sostenuto;
+        f(pedals);
+        let pedals = *pedals;
+
+        for voice in self.voices.iter_mut().filter(|voice| voice.channel() == Some(channel)) {
+            if pedals.sostenuto && !was_sostenuto {
+                voice.pedal_hold.sostenuto_latched =
+                    !voice.is_stolen() && !voice.is_released() && !voice.pedal_hold.release_pending;
+            }
+            if voice.pedal_hold.pedals_changed(pedals) {
+                voice.off(0.5);
             }
         }
     }
@@ -168,33 +896,166 @@ impl PolySynth {
             return;
         }
 
+        // CLAP requires a specific key for note-ons, a wildcard would start every key at once
+        let Some(key) = event.key().into_specific() else {
+            return;
+        };
         let channel = event.channel().into_specific().unwrap_or(0);
-        let keys = event
-            .key()
-            .into_specific()
-            .map(Range::single)
-            .unwrap_or(0..128);
-
-        for key in keys {
-            self.voices.push_back(Voice::new_host(
-                params,
-                self.sample_rate,
-                channel,
-                key,
-                event.note_id().into_specific(),
-                event.velocity() as f32,
-            ));
+
+        if params.get_global_lfo().map(|it| it.value).retrigger() == LfoRetrigger::Note {
+            self.global_lfo.retrigger(0.0);
+        }
+
+        let note = HeldNote {
+            channel,
+            key,
+            id: event.note_id().into_specific(),
+            velocity: event.velocity() as f32,
+        };
+        self.held_notes.push(note);
+
+        let play_mode = params.get_play_mode();
+        if play_mode == PlayMode::Poly {
+            self.make_room_for_voice(channel, key, params);
+            let voice = self.new_voice(params, &note);
+            // `make_room_for_voice` always leaves a free slot
+            let _ = self.voices.insert(voice);
+        } else {
+            self.update_mono_voice(params, 1.0);
         }
     }
 
-    pub fn handle_note_off_event(&mut self, event: &NoteOffEvent) {
+    fn new_voice(&mut self, params: &SchoffhauzerSynthPluginParams, note: &HeldNote) -> Voice {
+        self.next_voice_age += 1;
+        let lfo = params.get_voice_lfo().map(|it| it.value);
+        let lfo_phase = match lfo.retrigger() {
+            LfoRetrigger::Free => lfo.free_phase(self.song_position, self.seconds, self.tempo()),
+            LfoRetrigger::Note => 0.0,
+        };
+        let start = VoiceStart {
+            sample_rate: self.sample_rate,
+            age: self.next_voice_age,
+            pitch_bend: self.pitch_bend(note.channel),
+            lfo_phase,
+        };
+        Voice::new_host(params, note, start)
+    }
+
+    /// Makes the single mono voice follow the held note stack.
+    /// `release_velocity` is used when the last held note is released.
+    fn update_mono_voice(&mut self, params: &SchoffhauzerSynthPluginParams, release_velocity: f32) {
+        let Some(target) = self.held_notes.select(params.get_note_priority()) else {
+            let pedals = &self.pedals;
+            self.voices
+                .iter_mut()
+                .filter(|voice| !voice.is_stolen())
+                .for_each(|voice| voice.release(release_velocity, pedals));
+            return;
+        };
+
+        let pitch_bend = self.pitch_bend(target.channel);
+        let current = self
+            .voices
+            .iter_mut()
+            .filter(|voice| !voice.is_stolen())
+            .max_by_key(|voice| voice.age);
+        if let Some(voice) = current
+            && !voice.is_released()
+        {
+            if voice.is_host_note(&target) {
+                return;
+            }
+            if params.get_play_mode() == PlayMode::Legato {
+                voice.retarget(&target, pitch_bend);
+                return;
+            }
+        }
+
+        let glide = self
+            .voices
+            .iter()
+            .filter(|voice| !voice.is_stolen())
+            .max_by_key(|voice| voice.age)
+            .map_or(0.0, |voice| voice.glide_to(MidiNote(target.key).freq()));
+        self.voices
+            .iter_mut()
+            .filter(|voice| !voice.is_stolen())
+            .for_each(Voice::steal);
+        self.make_room_for_voice(target.channel, target.key, params);
+        let mut voice = self.new_voice(params, &target);
+        voice.glide = glide;
+        let _ = self.voices.insert(voice);
+    }
+
+    /// Steals voices until a new one fits within the polyphony limit.
+    fn make_room_for_voice(&mut self, channel: u16, key: u16, params: &SchoffhauzerSynthPluginParams) {
+        let max_polyphony = params.get_max_polyphony();
+        let stealing = params.get_voice_stealing();
+
+        if stealing == VoiceStealing::SameKey {
+            self.voices
+                .iter_mut()
+                .filter(|voice| !voice.is_stolen() && voice.is_host_key(channel, key))
+                .for_each(Voice::steal);
+        }
+
+        while self.voices.iter().filter(|voice| !voice.is_stolen()).count() >= max_polyphony {
+            self.voices
+                .iter_mut()
+                .filter(|voice| !voice.is_stolen())
+                .min_by(|a, b| stealing.compare(a, b))
+                .unwrap()
+                .steal();
+        }
+
+        // Fading voices still cost CPU, so dense passages drop the oldest fades outright
+        while self.voices.len() >= max_polyphony * 2 || self.voices.is_full() {
+            let oldest_stolen = self
+                .voices
+                .iter()
+                .enumerate()
+                .filter(|(_, voice)| voice.is_stolen())
+                .min_by_key(|(_, voice)| voice.age)
+                .map(|(index, _)| index)
+                .unwrap();
+            self.voices.remove(oldest_stolen);
+        }
+    }
+
+    fn pitch_bend(&self, channel: u16) -> f32 {
+        self.pitch_bends.get(channel as usize).copied().unwrap_or(0.0)
+    }
+
+    pub fn set_pitch_bend(&mut self, channel: u16, semitones: f32) {
+        let Some(pitch_bend) = self.pitch_bends.get_mut(channel as usize) else {
+            return;
+        };
+        *pitch_bend = semitones;
+        self.voices
+            .iter_mut()
+            .filter(|voice| voice.channel() == Some(channel))
+            .for_each(|voice| voice.pitch_bend = semitones);
+    }
+
+    pub fn handle_note_off_event(
+        &mut self,
+        event: &NoteOffEvent,
+        params: &SchoffhauzerSynthPluginParams,
+    ) {
         if !event.port_index().matches(0u16) {
             return;
         }
 
-        self.for_each_matching_voice(&HostNoteMatch::from(event), |voice| {
-            voice.off(event.velocity() as f32);
-        });
+        let note_match = HostNoteMatch::from(event);
+        self.held_notes.remove_matching(&note_match);
+        if params.get_play_mode() == PlayMode::Poly {
+            let pedals = self.pedals;
+            self.for_each_matching_voice(&note_match, |voice| {
+                voice.release(event.velocity() as f32, &pedals);
+            });
+        } else {
+            self.update_mono_voice(params, event.velocity() as f32);
+        }
     }
 
     pub fn handle_note_choke_event(&mut self, event: &NoteChokeEvent) {
@@ -202,11 +1063,23 @@ impl PolySynth {
             return;
         }
 
-        self.for_each_matching_voice(&HostNoteMatch::from(event), |voice| {
+        let note_match = HostNoteMatch::from(event);
+        self.held_notes.remove_matching(&note_match);
+        self.for_each_matching_voice(&note_match, |voice| {
             voice.choke();
         })
     }
 
+    pub fn handle_note_expression_event(&mut self, event: &NoteExpressionEvent) {
+        if !event.port_index().matches(0u16) {
+            return;
+        }
+
+        self.for_each_matching_voice(&HostNoteMatch::from(event), |voice| {
+            voice.expressions.apply(event);
+        })
+    }
+
     repetitive! {
         @for ty in ['value, 'modulation] {
             @let [event_name, event_type, event_method] = match ty {
@@ -229,9 +1102,99 @@ impl PolySynth {
                             })
                         }
                     }
-                    __ if __ == Some(SchoffhauzerSynthPluginParams::HF_ROLLOFF.id) => {
+                    @for osc in [0, 1, 2] {
+                        @for field in ['fine, 'level, 'hf_rolloff] {
+                            __ if __ == Some(SchoffhauzerSynthPluginParams::OSCILLATORS[@osc].@field.id) => {
+                                self.for_each_matching_voice(&note_match, |voice| {
+                                    voice.oscillators[@osc].@field.@ty = Some(event.@event_method() as f32);
+                                })
+                            }
+                        }
+                    }
+                    @for link in [0, 1] {
+                        __ if __ == Some(SchoffhauzerSynthPluginParams::OSCILLATOR_LINKS[@link].ring.id) => {
+                            self.for_each_matching_voice(&note_match, |voice| {
+                                voice.oscillator_links[@link].ring.@ty = Some(event.@event_method() as f32);
+                            })
+                        }
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::FILTER_CUTOFF.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.filter_cutoff.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::FILTER_RESONANCE.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.filter_resonance.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    @for field in ['attack_duration, 'attack_power, 'decay_duration, 'decay_power, 'sustain, 'release_duration, 'release_power] {
+                        __ if __ == Some(SchoffhauzerSynthPluginParams::MOD_ENV.@field.id) => {
+                            self.for_each_matching_voice(&note_match, |voice| {
+                                voice.mod_env.@field.@ty = Some(event.@event_method() as f32);
+                            })
+                        }
+                    }
+                    @for field in ['rate, 'fade_in, 'amount] {
+                        __ if __ == Some(SchoffhauzerSynthPluginParams::VOICE_LFO.@field.id) => {
+                            self.for_each_matching_voice(&note_match, |voice| {
+                                voice.lfo.@field.@ty = Some(event.@event_method() as f32);
+                            })
+                        }
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::MOD_ENV_AMOUNT.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.mod_env_amount.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::SUB_LEVEL.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.sub_level.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::VELOCITY_AMOUNT.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.velocity_amount.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::VELOCITY_HF_ROLLOFF.id) => {
                         self.for_each_matching_voice(&note_match, |voice| {
-                            voice.hf_rolloff.@ty = Some(event.@event_method() as f32);
+                            voice.velocity_hf_rolloff.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::VELOCITY_RELEASE.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.velocity_release.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::PORTAMENTO.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.portamento.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::PRESSURE_AMOUNT.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.pressure_amount.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::UNISON_DETUNE.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.unison_detune.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::UNISON_SPREAD.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.unison_spread.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::PULSE_WIDTH.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.pulse_width.@ty = Some(event.@event_method() as f32);
+                        })
+                    }
+                    __ if __ == Some(SchoffhauzerSynthPluginParams::PAN.id) => {
+                        self.for_each_matching_voice(&note_match, |voice| {
+                            voice.pan.@ty = Some(event.@event_method() as f32);
                         })
                     }
                     _ => {}
@@ -243,4 +1206,9 @@ impl PolySynth {
     pub fn is_busy(&self) -> bool {
         !self.voices.is_empty()
     }
+
+    /// Amplitude envelope of every sounding voice.
+    pub fn envelopes(&self) -> impl Iterator<Item = &ADSRInstance> + Clone {
+        self.voices.iter().map(|voice| &voice.adsr_instance)
+    }
 }
@@ -1,14 +1,70 @@
+use crate::utils::param_enum::ParamEnum;
+use derive_more::Display;
 use std::f32::consts::PI;
 
+/// Leak of the triangle integrator per unit of `freq / sample_rate`,
+/// forgets the DC offset of the start transient with a time constant of ten periods at any pitch
+const TRIANGLE_LEAK: f32 = 0.1;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum Waveform {
+    #[default]
+    Saw,
+    /// Difference of two saws half a period apart
+    Square,
+    /// Integrated [`Waveform::Square`]
+    Triangle,
+    Sine,
+    /// Like [`Waveform::Square`], high for [`Synth::pulse_width`] of the period
+    Pulse,
+}
+
+impl ParamEnum for Waveform {
+    const ALL: &'static [Self] = &[
+        Waveform::Saw,
+        Waveform::Square,
+        Waveform::Triangle,
+        Waveform::Sine,
+        Waveform::Pulse,
+    ];
+}
+
+/// State of one Schoffhauzer feedback-FM saw.
+#[derive(Default)]
+struct SawCore {
+    osc: f32,
+    last_osc: f32,
+    last_out: f32,
+}
+
+impl SawCore {
+    /// Next sample at `phase`, without the DC correction and normalization.
+    fn synth(&mut self, phase: f32, scaling: f32, hf_rolloff: f32) -> f32 {
+        self.osc = (self.osc + f32::sin(2.0 * PI * (phase + self.osc * scaling * hf_rolloff))) * 0.5;
+        let out = 2.5 * self.osc + -1.5 * self.last_osc;
+        self.last_osc = self.osc;
+
+        let last_out = self.last_out;
+        self.last_out = out;
+        (out + last_out) * 0.5
+    }
+}
+
 pub struct Synth {
     pub sample_rate: f32,
     pub freq: f32,
     pub hf_rolloff: f32,
+    /// `0.0..=1.0`, the pulse fades to silence towards either end
+    pub pulse_width: f32,
 
-    osc: f32,
-    last_osc: f32,
+    waveform: Waveform,
     phase: f32,
-    last_out: f32,
+    /// Fraction of the last sample after the phase wrapped, `None` if it didn't
+    wrap: Option<f32>,
+    saw: SawCore,
+    /// Runs behind `saw` for the pulse, square and triangle
+    shifted_saw: SawCore,
+    triangle: f32,
 }
 
 impl Synth {
@@ -17,13 +73,46 @@ impl Synth {
             sample_rate,
             freq,
             hf_rolloff: 1.0,
-            osc: 0.0,
-            last_osc: 0.0,
+            pulse_width: 0.5,
+            waveform: Waveform::default(),
             phase: 0.0,
-            last_out: 0.0,
+            wrap: None,
+            saw: SawCore::default(),
+            shifted_saw: SawCore::default(),
+            triangle: Self::triangle_at(0.0),
         }
     }
 
+    /// `phase` is in `-1.0..1.0`, like the internal phase accumulator.
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase;
+        self.triangle = Self::triangle_at(phase);
+    }
+
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        if waveform != self.waveform {
+            // The integrator is only advanced while playing a triangle
+            self.triangle = Self::triangle_at(self.phase);
+        }
+        self.waveform = waveform;
+    }
+
+    pub fn wrap(&self) -> Option<f32> {
+        self.wrap
+    }
+
+    /// Restarts the period `fraction` of a sample before the next one, for hard sync to a [`Synth::wrap`].
+    /// The feedback state is kept, so the reset is smoothed like the saw's own reset.
+    pub fn sync(&mut self, fraction: f32) {
+        // The next `synth` call advances the phase by a whole sample first
+        self.phase = -1.0 + (fraction - 1.0) * 2.0 * self.freq / self.sample_rate;
+    }
+
+    /// The ideal triangle at `phase`, the square is high while `phase` is positive.
+    fn triangle_at(phase: f32) -> f32 {
+        2.0 * phase.abs() - 1.0
+    }
+
     pub fn synth(&mut self) -> f32 {
         let w = self.freq / self.sample_rate;
         let n = 0.5 - w;
@@ -31,19 +120,35 @@ impl Synth {
         let dc = 0.376 - w * 0.752;
 
         self.phase += 2.0 * w;
+        self.wrap = None;
         if self.phase >= 1.0 {
             self.phase -= 2.0;
+            self.wrap = Some((self.phase + 1.0) / (2.0 * w));
         }
 
-        self.osc = (self.osc + f32::sin(2.0 * PI * (self.phase + self.osc * scaling * self.hf_rolloff))) * 0.5;
-        let mut out = 2.5 * self.osc + -1.5 * self.last_osc;
-        self.last_osc = self.osc;
-
-        let last_out = self.last_out;
-        self.last_out = out;
-        out = (out + last_out) * 0.5;
-
-        out += dc;
-        out * (1.0 - 2.0 * w) // normalize
+        let normalize = 1.0 - 2.0 * w;
+        let saw = self.saw.synth(self.phase, scaling, self.hf_rolloff);
+        match self.waveform {
+            Waveform::Saw => (saw + dc) * normalize,
+            Waveform::Square | Waveform::Triangle | Waveform::Pulse => {
+                let pulse_width = match self.waveform {
+                    Waveform::Pulse => self.pulse_width.clamp(0.0, 1.0),
+                    _ => 0.5,
+                };
+                // The difference of two falling saws is high for the part of the period not covered by the offset,
+                // and has no DC at any offset since both saws share the same mean
+                let mut shifted_phase = self.phase + 2.0 * (1.0 - pulse_width);
+                if shifted_phase >= 1.0 {
+                    shifted_phase -= 2.0;
+                }
+                let square = (saw - self.shifted_saw.synth(shifted_phase, scaling, self.hf_rolloff)) * normalize;
+                if self.waveform != Waveform::Triangle {
+                    return square;
+                }
+                self.triangle = self.triangle * (1.0 - TRIANGLE_LEAK * w) + square * 4.0 * w;
+                self.triangle
+            }
+            Waveform::Sine => f32::sin(PI * self.phase),
+        }
     }
 }
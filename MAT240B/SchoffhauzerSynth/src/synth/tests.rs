@@ -0,0 +1,878 @@
+//! Golden-audio and spectral regression tests for the Schoffhauzer oscillator, response tests of the filter,
+//! tests of the MIDI decoding and note handling, plus a concurrency check of the parameters.
+//!
+//! Reference renders live in `tests/golden`. After an intentional change to the sound,
+//! regenerate them with `SCHOFFHAUZER_BLESS=1 cargo test` and listen to the diff before committing.
+
+use crate::offline::renderer::OfflineRenderer;
+use crate::offline::script::{ScriptEvent, ScriptEventKind};
+use crate::offline::wav::{Wav, read_wav_file, write_wav_file};
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::synth::filter::{Filter, FilterMode, FilterSlope, MIN_DAMPING};
+use crate::synth::midi::{CC_MOD_WHEEL, CC_SOSTENUTO, CC_SUSTAIN, MidiMessage};
+use crate::synth::mod_matrix::ModSource;
+use crate::synth::pedals::{ChannelPedals, PedalHold};
+use crate::synth::poly_synth::PolySynth;
+use crate::synth::synth::{Synth, Waveform};
+use crate::utils::alloc_guard::forbid_alloc;
+use crate::utils::envelope::ADSRPhase;
+use crate::utils::fft::magnitude_spectrum;
+use crate::utils::midi_note::MidiNote;
+use crate::utils::param_enum::ParamEnum;
+use clack_extensions::params::ParamInfo;
+use clack_plugin::events::{Match, Pckn};
+use clack_plugin::events::event_types::{NoteOnEvent, ParamValueEvent};
+use clack_plugin::utils::Cookie;
+use std::f32::consts::PI;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const SAMPLE_RATES: [u32; 3] = [44100, 48000, 96000];
+const NOTES: [u16; 3] = [33, 69, 93];
+const HF_ROLLOFFS: [f32; 3] = [0.0, 0.5, 1.0];
+
+const GOLDEN_LENGTH: usize = 2048;
+const GOLDEN_TOLERANCE: f32 = 1e-4;
+
+/// Samples skipped before analysis, so the feedback loop and filters have settled
+const SETTLE: usize = 1024;
+/// Long enough that the bins around each harmonic are narrow next to the spacing of the lowest note
+const SPECTRUM_SIZE: usize = 16384;
+
+/// Largest DC offset of the saw at full feedback for each of [`NOTES`], the correction is tuned for the middle
+const DC_LIMITS: [f32; 3] = [0.055, 0.02, 0.042];
+/// The saw measures between -43 and -48 dB over [`SAMPLE_RATES`] and [`NOTES`]
+const ALIAS_LIMIT: f32 = -40.0;
+/// The square, pulse, triangle and sine measure between -43 and -56 dB
+const WAVEFORM_ALIAS_LIMIT: f32 = -40.0;
+/// Towards either end the pulse is the difference of two nearly equal saws, their aliasing stays
+/// while the signal shrinks. At widths of 0.02 and 0.98 it measures between -28 and -65 dB
+const NARROW_PULSE_ALIAS_LIMIT: f32 = -25.0;
+/// Unlike the saw, the other waveforms are built without a DC offset
+const WAVEFORM_DC_LIMIT: f32 = 0.005;
+/// The triangle's integrator starts with an offset of up to 0.2 at low notes,
+/// which it forgets with a time constant of ten periods
+const TRIANGLE_SETTLE_PERIODS: f32 = 50.0;
+/// Every waveform besides the saw, with the pulse width used for [`Waveform::Pulse`]
+const WAVEFORMS: [(Waveform, f32); 4] =
+    [(Waveform::Square, 0.5), (Waveform::Triangle, 0.5), (Waveform::Sine, 0.5), (Waveform::Pulse, 0.25)];
+/// Pulse widths near either end, where most of the period is high or low
+const NARROW_PULSE_WIDTHS: [f32; 2] = [0.02, 0.98];
+/// Hard sync restarts the slave mid-period without band limiting the step, so it aliases far more than a free running
+/// oscillator, the most at high notes where the harmonics are sparse. For each of [`NOTES`] the saw, square and sine
+/// measure at worst -30, -20 and -16 dB over [`SYNC_RATIOS`]
+const SYNC_ALIAS_LIMITS: [f32; 3] = [-27.0, -17.0, -13.0];
+/// Slave to master frequency ratios
+const SYNC_RATIOS: [f32; 3] = [1.5, 2.37, 3.7];
+/// Syncing at the fraction of the sample where the master wrapped, rather than on the next sample,
+/// aliases at least 2.5 dB less at the highest of [`NOTES`]
+const SYNC_FRACTION_GAIN: f32 = 1.5;
+const FILTER_SAMPLE_RATE: u32 = 48000;
+const FILTER_CUTOFF: f32 = 1000.0;
+/// Enough for the transient of a sine a decade below the cutoff to die out
+const FILTER_SETTLE: usize = 4800;
+const FILTER_SLOPES: [FilterSlope; 2] = [FilterSlope::Db12, FilterSlope::Db24];
+/// Bins on either side of a harmonic that count towards it. The Hann window leaks a pure sine
+/// to about -30 dB past 2 bins, which hid any aliasing below that, but only to about -46 dB past 4.
+const HARMONIC_LOBE: f32 = 4.0;
+
+fn render_synth(sample_rate: u32, note: u16, hf_rolloff: f32, length: usize) -> Vec<f32> {
+    let mut synth = Synth::new(sample_rate as f32, MidiNote(note).freq());
+    synth.hf_rolloff = hf_rolloff;
+    (0..length).map(|_| synth.synth()).collect()
+}
+
+fn render_waveform(sample_rate: u32, note: u16, waveform: Waveform, pulse_width: f32, length: usize) -> Vec<f32> {
+    let mut synth = Synth::new(sample_rate as f32, MidiNote(note).freq());
+    synth.set_waveform(waveform);
+    synth.pulse_width = pulse_width;
+    (0..length).map(|_| synth.synth()).collect()
+}
+
+/// Renders a slave at `ratio` times the note hard synced to a saw at the note,
+/// either at the fraction of the sample where the master wrapped or at the start of the next sample.
+fn render_synced(sample_rate: u32, note: u16, ratio: f32, waveform: Waveform, fractional: bool, length: usize) -> Vec<f32> {
+    let freq = MidiNote(note).freq();
+    let mut master = Synth::new(sample_rate as f32, freq);
+    let mut slave = Synth::new(sample_rate as f32, freq * ratio);
+    slave.set_waveform(waveform);
+    (0..length)
+        .map(|_| {
+            master.synth();
+            if let Some(fraction) = master.wrap() {
+                slave.sync(if fractional { fraction } else { 1.0 });
+            }
+            slave.synth()
+        })
+        .collect()
+}
+
+fn configured_filter(mode: FilterMode, slope: FilterSlope, resonance: f32) -> Filter {
+    let mut filter = Filter::new(FILTER_SAMPLE_RATE as f32);
+    filter.configure(mode, slope, FILTER_CUTOFF, resonance);
+    filter
+}
+
+/// Gain in dB of the filter for a sine at `freq` once it settled, over whole periods.
+fn filter_gain_db(mode: FilterMode, slope: FilterSlope, resonance: f32, freq: f32) -> f32 {
+    let mut filter = configured_filter(mode, slope, resonance);
+    let period = FILTER_SAMPLE_RATE as f32 / freq;
+    let length = ((SPECTRUM_SIZE as f32 / period).floor() * period).round() as usize;
+    let (mut input_energy, mut output_energy) = (0.0f64, 0.0f64);
+    for i in 0..FILTER_SETTLE + length {
+        let input = f32::sin(2.0 * PI * freq * i as f32 / FILTER_SAMPLE_RATE as f32);
+        let (output, _) = filter.process(input, input);
+        if i >= FILTER_SETTLE {
+            input_energy += (input * input) as f64;
+            output_energy += (output * output) as f64;
+        }
+    }
+    (10.0 * (output_energy / input_energy).log10()) as f32
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.wav"))
+}
+
+/// Compares `channels` against the named golden file, or rewrites it in bless mode.
+fn check_golden(name: &str, sample_rate: u32, channels: &[Vec<f32>]) {
+    let path = golden_path(name);
+    if std::env::var_os("SCHOFFHAUZER_BLESS").is_some() {
+        let channels = channels.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        write_wav_file(&path, sample_rate, &channels).unwrap();
+        return;
+    }
+
+    let Wav { sample_rate: golden_sample_rate, channels: golden } = read_wav_file(&path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", path.display()));
+    assert_eq!(golden_sample_rate, sample_rate, "{name}: sample rate mismatch");
+    assert_eq!(golden.len(), channels.len(), "{name}: channel count mismatch");
+    for (channel, (expected, actual)) in golden.iter().zip(channels).enumerate() {
+        assert_eq!(expected.len(), actual.len(), "{name}: length mismatch");
+        for (i, (&expected, &actual)) in expected.iter().zip(actual).enumerate() {
+            assert!(
+                (expected - actual).abs() <= GOLDEN_TOLERANCE,
+                "{name}: channel {channel} sample {i} is {actual}, expected {expected}"
+            );
+        }
+    }
+}
+
+/// Hann-windowed power spectrum of the first [`SPECTRUM_SIZE`] samples with the mean removed,
+/// bin `k` is `k * sr / N` Hz.
+fn power_spectrum(samples: &[f32]) -> Vec<f32> {
+    let samples = &samples[..SPECTRUM_SIZE];
+    let mean = samples.iter().sum::<f32>() / SPECTRUM_SIZE as f32;
+    let samples = samples.iter().map(|sample| sample - mean).collect::<Vec<_>>();
+    magnitude_spectrum(&samples).iter().map(|magnitude| magnitude * magnitude).collect()
+}
+
+fn bin_freq(bin: usize, sample_rate: u32) -> f32 {
+    bin as f32 * sample_rate as f32 / SPECTRUM_SIZE as f32
+}
+
+/// Energy outside the [`HARMONIC_LOBE`] of each harmonic of `freq` relative to the energy inside them, in dB.
+fn alias_db(samples: &[f32], sample_rate: u32, freq: f32) -> f32 {
+    let spectrum = power_spectrum(samples);
+    let (mut harmonic_energy, mut alias_energy) = (0.0f32, 0.0f32);
+    for (bin, &power) in spectrum.iter().enumerate() {
+        let harmonic = (bin_freq(bin, sample_rate) / freq).round();
+        let distance = (bin_freq(bin, sample_rate) - harmonic * freq).abs();
+        if harmonic == 0.0 {
+            continue;
+        }
+        if distance <= HARMONIC_LOBE * bin_freq(1, sample_rate) {
+            harmonic_energy += power;
+        } else {
+            alias_energy += power;
+        }
+    }
+    10.0 * (alias_energy / harmonic_energy).log10()
+}
+
+#[test]
+fn oscillator_matches_golden() {
+    for sample_rate in SAMPLE_RATES {
+        let channels = NOTES
+            .iter()
+            .flat_map(|&note| {
+                HF_ROLLOFFS
+                    .iter()
+                    .map(move |&hf_rolloff| render_synth(sample_rate, note, hf_rolloff, GOLDEN_LENGTH))
+            })
+            .collect::<Vec<_>>();
+        check_golden(&format!("oscillator_{sample_rate}"), sample_rate, &channels);
+    }
+}
+
+#[test]
+fn poly_synth_matches_golden() {
+    let volume = SchoffhauzerSynthPluginParams::VOLUME.id.get();
+    let hf_rolloff = SchoffhauzerSynthPluginParams::HF_ROLLOFF.id.get();
+    let events = [
+        ScriptEvent {
+            time: 0,
+            kind: ScriptEventKind::NoteOn { channel: 0, key: 48, note_id: None, velocity: 1.0 },
+        },
+        ScriptEvent {
+            time: 2400,
+            kind: ScriptEventKind::NoteOn { channel: 0, key: 64, note_id: Some(7), velocity: 1.0 },
+        },
+        ScriptEvent {
+            time: 4800,
+            kind: ScriptEventKind::ParamMod {
+                param_id: volume,
+                channel: None,
+                key: None,
+                note_id: Some(7),
+                amount: -6.0,
+            },
+        },
+        ScriptEvent {
+            time: 9600,
+            kind: ScriptEventKind::ParamValue {
+                param_id: hf_rolloff,
+                channel: None,
+                key: None,
+                note_id: None,
+                value: 0.3,
+            },
+        },
+        ScriptEvent {
+            time: 14400,
+            kind: ScriptEventKind::NoteOff { channel: None, key: Some(48), note_id: None, velocity: 1.0 },
+        },
+        ScriptEvent {
+            time: 16800,
+            kind: ScriptEventKind::NoteOff { channel: None, key: None, note_id: Some(7), velocity: 1.0 },
+        },
+    ];
+
+    for sample_rate in [44100, 48000] {
+        let [left, right] = OfflineRenderer::new(sample_rate as f32).render(&events, 36000);
+        // Centered voices at full width are identical on both channels and match the mono reference
+        assert_eq!(left, right, "poly_synth_{sample_rate}: channels differ");
+        check_golden(&format!("poly_synth_{sample_rate}"), sample_rate, &[left]);
+    }
+}
+
+/// The DC correction is tuned for full feedback,
+/// lower `hf_rolloff` values drift towards a sine with an offset, so only that case is checked.
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn oscillator_has_no_dc_offset() {
+    for sample_rate in SAMPLE_RATES {
+        for (note, limit) in NOTES.into_iter().zip(DC_LIMITS) {
+            let freq = MidiNote(note).freq();
+            // Average over a whole number of periods
+            let period = sample_rate as f32 / freq;
+            let length = ((SPECTRUM_SIZE as f32 / period).floor() * period).round() as usize;
+            let samples = render_synth(sample_rate, note, 1.0, SETTLE + length);
+            let dc = samples[SETTLE..].iter().sum::<f32>() / length as f32;
+            assert!(dc.abs() < limit, "DC offset {dc} at {sample_rate}Hz, note {note}");
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn oscillator_fundamental_matches_note() {
+    for sample_rate in SAMPLE_RATES {
+        for note in NOTES {
+            for hf_rolloff in HF_ROLLOFFS {
+                let freq = MidiNote(note).freq();
+                let samples = render_synth(sample_rate, note, hf_rolloff, SETTLE + SPECTRUM_SIZE);
+                let spectrum = power_spectrum(&samples[SETTLE..]);
+                let peak = (1..spectrum.len())
+                    .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+                    .unwrap();
+                let bin_width = bin_freq(1, sample_rate);
+                assert!(
+                    (bin_freq(peak, sample_rate) - freq).abs() <= bin_width,
+                    "peak at {}Hz, expected {freq}Hz ({sample_rate}Hz, hf_rolloff {hf_rolloff})",
+                    bin_freq(peak, sample_rate)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn oscillator_aliasing_is_low() {
+    for sample_rate in SAMPLE_RATES {
+        for note in NOTES {
+            let freq = MidiNote(note).freq();
+            let samples = render_synth(sample_rate, note, 1.0, SETTLE + SPECTRUM_SIZE);
+            let alias_db = alias_db(&samples[SETTLE..], sample_rate, freq);
+            assert!(
+                alias_db < ALIAS_LIMIT,
+                "aliasing at {alias_db:.1}dB ({sample_rate}Hz, note {note})"
+            );
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn waveforms_have_no_dc_offset() {
+    let narrow_pulses = NARROW_PULSE_WIDTHS.map(|width| (Waveform::Pulse, width));
+    for sample_rate in SAMPLE_RATES {
+        for note in NOTES {
+            for (waveform, pulse_width) in WAVEFORMS.into_iter().chain(narrow_pulses) {
+                let period = sample_rate as f32 / MidiNote(note).freq();
+                let settle = match waveform {
+                    Waveform::Triangle => (TRIANGLE_SETTLE_PERIODS * period) as usize,
+                    _ => SETTLE,
+                };
+                let length = ((SPECTRUM_SIZE as f32 / period).floor() * period).round() as usize;
+                let samples = render_waveform(sample_rate, note, waveform, pulse_width, settle + length);
+                let dc = samples[settle..].iter().sum::<f32>() / length as f32;
+                assert!(
+                    dc.abs() < WAVEFORM_DC_LIMIT,
+                    "DC offset {dc} of {waveform} ({pulse_width}) at {sample_rate}Hz, note {note}"
+                );
+            }
+        }
+    }
+}
+
+/// Narrow pulses are left out, their lowest harmonics are about equally strong.
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn waveform_fundamentals_match_note() {
+    for sample_rate in SAMPLE_RATES {
+        for note in NOTES {
+            for (waveform, pulse_width) in WAVEFORMS {
+                let freq = MidiNote(note).freq();
+                let samples = render_waveform(sample_rate, note, waveform, pulse_width, SETTLE + SPECTRUM_SIZE);
+                let spectrum = power_spectrum(&samples[SETTLE..]);
+                let peak = (1..spectrum.len())
+                    .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+                    .unwrap();
+                assert!(
+                    (bin_freq(peak, sample_rate) - freq).abs() <= bin_freq(1, sample_rate),
+                    "{waveform} peaks at {}Hz, expected {freq}Hz ({sample_rate}Hz)",
+                    bin_freq(peak, sample_rate)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn waveform_aliasing_is_low() {
+    let narrow_pulses = NARROW_PULSE_WIDTHS.map(|width| ((Waveform::Pulse, width), NARROW_PULSE_ALIAS_LIMIT));
+    let waveforms = WAVEFORMS.map(|waveform| (waveform, WAVEFORM_ALIAS_LIMIT));
+    for sample_rate in SAMPLE_RATES {
+        for note in NOTES {
+            for ((waveform, pulse_width), limit) in waveforms.into_iter().chain(narrow_pulses) {
+                let freq = MidiNote(note).freq();
+                let samples = render_waveform(sample_rate, note, waveform, pulse_width, SETTLE + SPECTRUM_SIZE);
+                let alias_db = alias_db(&samples[SETTLE..], sample_rate, freq);
+                assert!(
+                    alias_db < limit,
+                    "aliasing of {waveform} ({pulse_width}) at {alias_db:.1}dB ({sample_rate}Hz, note {note})"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn pulse_is_silent_at_either_end() {
+    for pulse_width in [0.0, 1.0] {
+        let samples = render_waveform(48000, 69, Waveform::Pulse, pulse_width, SETTLE + SPECTRUM_SIZE);
+        let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak < 1e-4, "pulse width {pulse_width} peaks at {peak}");
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn hard_sync_aliasing_is_bounded() {
+    for sample_rate in SAMPLE_RATES {
+        for (note, limit) in NOTES.into_iter().zip(SYNC_ALIAS_LIMITS) {
+            for ratio in SYNC_RATIOS {
+                for waveform in [Waveform::Saw, Waveform::Square, Waveform::Sine] {
+                    let freq = MidiNote(note).freq();
+                    let samples = render_synced(sample_rate, note, ratio, waveform, true, SETTLE + SPECTRUM_SIZE);
+                    // The synced slave repeats with the master's period
+                    let alias_db = alias_db(&samples[SETTLE..], sample_rate, freq);
+                    assert!(
+                        alias_db < limit,
+                        "aliasing of {waveform} synced at {ratio} at {alias_db:.1}dB ({sample_rate}Hz, note {note})"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "the baseline saw core plays an octave above the note"]
+fn hard_sync_at_the_wrap_fraction_aliases_less() {
+    let note = NOTES[NOTES.len() - 1];
+    let freq = MidiNote(note).freq();
+    for sample_rate in SAMPLE_RATES {
+        for ratio in SYNC_RATIOS {
+            for waveform in [Waveform::Saw, Waveform::Square, Waveform::Sine] {
+                let [fractional, next_sample] = [true, false].map(|fractional| {
+                    let samples = render_synced(sample_rate, note, ratio, waveform, fractional, SETTLE + SPECTRUM_SIZE);
+                    alias_db(&samples[SETTLE..], sample_rate, freq)
+                });
+                assert!(
+                    fractional < next_sample - SYNC_FRACTION_GAIN,
+                    "{waveform} synced at {ratio}: {fractional:.1}dB, {next_sample:.1}dB on the next sample ({sample_rate}Hz)"
+                );
+            }
+        }
+    }
+}
+
+/// A single stage passes `1 / k` at the cutoff for damping `k`,
+/// the 24 dB slope adds a first stage without resonance that passes `1 / sqrt(2)`.
+#[test]
+fn filter_gain_at_cutoff() {
+    for (resonance, damping) in [(0.0, 2.0), (1.0, MIN_DAMPING)] {
+        for slope in FILTER_SLOPES {
+            let stages_gain = match slope {
+                FilterSlope::Db12 => 1.0 / damping,
+                FilterSlope::Db24 => 1.0 / (damping * 2.0f32.sqrt()),
+            };
+            let expected = 20.0 * stages_gain.log10();
+            for mode in [FilterMode::LowPass, FilterMode::BandPass, FilterMode::HighPass] {
+                let gain = filter_gain_db(mode, slope, resonance, FILTER_CUTOFF);
+                assert!(
+                    (gain - expected).abs() < 0.1,
+                    "{mode} {slope} at resonance {resonance}: {gain:.2}dB, expected {expected:.2}dB"
+                );
+            }
+            let notch = filter_gain_db(FilterMode::Notch, slope, resonance, FILTER_CUTOFF);
+            assert!(notch < -60.0, "Notch {slope} at resonance {resonance}: {notch:.2}dB");
+        }
+    }
+}
+
+#[test]
+fn filter_passband_is_flat() {
+    let passbands = [
+        (FilterMode::Off, FILTER_CUTOFF),
+        (FilterMode::LowPass, FILTER_CUTOFF / 10.0),
+        (FilterMode::HighPass, FILTER_CUTOFF * 10.0),
+        (FilterMode::Notch, FILTER_CUTOFF / 10.0),
+        (FilterMode::Notch, FILTER_CUTOFF * 10.0),
+    ];
+    for resonance in [0.0, 1.0] {
+        for slope in FILTER_SLOPES {
+            for (mode, freq) in passbands {
+                let gain = filter_gain_db(mode, slope, resonance, freq);
+                assert!(gain.abs() < 0.5, "{mode} {slope} at {freq}Hz, resonance {resonance}: {gain:.2}dB");
+            }
+        }
+    }
+}
+
+/// A decade from the cutoff, each stage attenuates by about 40 dB, or 20 dB for the band pass
+#[test]
+fn filter_stopband_attenuates() {
+    let stopbands = [
+        (FilterMode::LowPass, FILTER_CUTOFF * 10.0, 36.0),
+        (FilterMode::HighPass, FILTER_CUTOFF / 10.0, 36.0),
+        (FilterMode::BandPass, FILTER_CUTOFF / 10.0, 18.0),
+        (FilterMode::BandPass, FILTER_CUTOFF * 10.0, 18.0),
+    ];
+    for resonance in [0.0, 1.0] {
+        for slope in FILTER_SLOPES {
+            let stages = match slope {
+                FilterSlope::Db12 => 1.0,
+                FilterSlope::Db24 => 2.0,
+            };
+            for (mode, freq, attenuation) in stopbands {
+                let gain = filter_gain_db(mode, slope, resonance, freq);
+                assert!(
+                    gain < -attenuation * stages,
+                    "{mode} {slope} at {freq}Hz, resonance {resonance}: {gain:.2}dB"
+                );
+            }
+        }
+    }
+}
+
+/// Full resonance rings at the cutoff but stays just short of self-oscillation at any cutoff.
+#[test]
+fn filter_full_resonance_is_stable() {
+    for cutoff in [100.0, FILTER_CUTOFF, 10000.0, 23000.0] {
+        for slope in FILTER_SLOPES {
+            for mode in [FilterMode::LowPass, FilterMode::BandPass, FilterMode::HighPass, FilterMode::Notch] {
+                let mut filter = Filter::new(FILTER_SAMPLE_RATE as f32);
+                filter.configure(mode, slope, cutoff, 1.0);
+                // The impulse sits in the middle of the analysed window, where the Hann window is open,
+                // and is followed by about 16 time constants of the ringing at the lowest cutoff
+                let impulse = SPECTRUM_SIZE / 2;
+                let length = 8 * SPECTRUM_SIZE;
+                let response = (0..length)
+                    .map(|i| filter.process(if i == impulse { 1.0 } else { 0.0 }, 0.0).0)
+                    .collect::<Vec<_>>();
+                let peak = response.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+                let tail = response[length - 1024..].iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+                assert!(peak.is_finite() && tail < 1e-3 * peak, "{mode} {slope} at {cutoff}Hz: rings at {tail} of {peak}");
+
+                if mode == FilterMode::Notch || cutoff > FILTER_SAMPLE_RATE as f32 * 0.45 {
+                    continue;
+                }
+                let spectrum = power_spectrum(&response);
+                let resonant = (1..spectrum.len())
+                    .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+                    .unwrap();
+                let resonant = bin_freq(resonant, FILTER_SAMPLE_RATE);
+                assert!(
+                    (resonant - cutoff).abs() <= 2.0 * bin_freq(1, FILTER_SAMPLE_RATE),
+                    "{mode} {slope} at {cutoff}Hz resonates at {resonant}Hz"
+                );
+            }
+        }
+    }
+}
+
+/// The editor and the host's main thread write parameters while the audio thread renders,
+/// rendering has to keep going without locks and neither side may undo the other's writes.
+#[test]
+fn rendering_survives_concurrent_param_writes() {
+    type Params = SchoffhauzerSynthPluginParams;
+    let params = Arc::new(Params::default());
+    let mut synth = PolySynth::new(48000.0);
+    let note_on = ScriptEventKind::NoteOn { channel: 0, key: 60, note_id: None, velocity: 1.0 };
+    note_on.with_clack_event(0, |event| synth.handle_event(event, &params));
+
+    const BLOCKS: usize = 2000;
+    let value_at = |info: &ParamInfo<'static>, t: f64| info.min_value + (info.max_value - info.min_value) * t;
+    let set = move |params: &Params, info: &ParamInfo<'static>, t: f64| {
+        let event = ParamValueEvent::new(0, info.id, Pckn::match_all(), value_at(info, t), Cookie::empty());
+        params.handle_param_value_event(&event);
+    };
+    let done = Arc::new(AtomicBool::new(false));
+    let writer = std::thread::spawn({
+        let (params, done) = (params.clone(), done.clone());
+        move || {
+            let mut writes = 0u64;
+            while !done.load(Ordering::Relaxed) {
+                let t = (writes % 101) as f64 / 100.0;
+                set(&params, Params::VOLUME, t);
+                set(&params, Params::HF_ROLLOFF, t);
+                set(&params, Params::ADSR.sustain, t);
+                writes += 1;
+            }
+            set(&params, Params::ADSR.sustain, 0.25);
+            writes
+        }
+    });
+
+    let (mut left, mut right) = ([0.0; 256], [0.0; 256]);
+    for block in 0..BLOCKS {
+        // The audio thread writes its own events into the same envelope as the writer
+        set(&params, Params::ADSR.decay_duration, (block % 11) as f64 / 10.0);
+        left.fill(0.0);
+        right.fill(0.0);
+        forbid_alloc(|| synth.synth(&mut left, &mut right, &params));
+        assert!(left.iter().chain(&right).all(|sample| sample.is_finite()), "block {block} is not finite");
+    }
+    done.store(true, Ordering::Relaxed);
+    let writes = writer.join().unwrap();
+    assert!(writes > 0, "the writer never ran");
+
+    let adsr = params.get_adsr();
+    let last_decay = ((BLOCKS - 1) % 11) as f64 / 10.0;
+    assert_eq!(adsr.sustain.value, value_at(Params::ADSR.sustain, 0.25) as f32);
+    assert_eq!(adsr.decay_duration.value, value_at(Params::ADSR.decay_duration, last_decay) as f32);
+}
+
+#[test]
+fn wildcard_key_note_on_is_ignored() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let mut synth = PolySynth::new(48000.0);
+    let event = NoteOnEvent::new(0, Pckn::new(0u16, 0u16, Match::All, Match::All), 1.0);
+    synth.handle_note_on_event(&event, &params);
+    assert!(!synth.is_busy());
+}
+
+#[test]
+fn midi1_note_on_without_velocity_is_a_note_off() {
+    assert!(matches!(
+        MidiMessage::from_midi1([0x93, 60, 0]),
+        Some(MidiMessage::NoteOff { channel: 3, key: 60, velocity }) if velocity == 64.0 / 127.0
+    ));
+    assert!(matches!(
+        MidiMessage::from_midi1([0x93, 60, 127]),
+        Some(MidiMessage::NoteOn { channel: 3, key: 60, velocity }) if velocity == 1.0
+    ));
+}
+
+#[test]
+fn midi1_pitch_bend_is_14_bit() {
+    let bend = |lsb, msb| match MidiMessage::from_midi1([0xe5, lsb, msb]) {
+        Some(MidiMessage::PitchBend { channel: 5, bend }) => bend,
+        message => panic!("{message:?}"),
+    };
+    assert_eq!(bend(0x00, 0x40), 0.0);
+    assert_eq!(bend(0x01, 0x40), 1.0 / 8192.0);
+    assert_eq!(bend(0x00, 0x00), -1.0);
+    assert_eq!(bend(0x7f, 0x7f), 8191.0 / 8192.0);
+}
+
+#[test]
+fn midi1_ignores_other_messages() {
+    // Program change and timing clock
+    assert!(MidiMessage::from_midi1([0xc0, 5, 0]).is_none());
+    assert!(MidiMessage::from_midi1([0xf8, 0, 0]).is_none());
+}
+
+#[test]
+fn ump_midi1_packets_decode_like_midi1() {
+    // The group in the second nibble is ignored
+    for word0 in [0x2093_3c64, 0x2593_3c64] {
+        assert!(matches!(
+            MidiMessage::from_ump([word0, 0, 0, 0]),
+            Some(MidiMessage::NoteOn { channel: 3, key: 60, velocity }) if velocity == 100.0 / 127.0
+        ));
+    }
+}
+
+#[test]
+fn ump_midi2_note_on_keeps_zero_velocity() {
+    assert!(matches!(
+        MidiMessage::from_ump([0x4093_3c00, 0x0000_0000, 0, 0]),
+        Some(MidiMessage::NoteOn { channel: 3, key: 60, velocity }) if velocity == 0.0
+    ));
+    assert!(matches!(
+        MidiMessage::from_ump([0x4083_3c00, 0xffff_0000, 0, 0]),
+        Some(MidiMessage::NoteOff { channel: 3, key: 60, velocity }) if velocity == 1.0
+    ));
+}
+
+#[test]
+fn ump_midi2_values_are_32_bit() {
+    assert!(matches!(
+        MidiMessage::from_ump([0x40b3_4000, u32::MAX, 0, 0]),
+        Some(MidiMessage::ControlChange { channel: 3, controller: 64, value }) if value == 1.0
+    ));
+    let bend = |word1| match MidiMessage::from_ump([0x40e0_0000, word1, 0, 0]) {
+        Some(MidiMessage::PitchBend { channel: 0, bend }) => bend,
+        message => panic!("{message:?}"),
+    };
+    assert_eq!(bend(0x8000_0000), 0.0);
+    assert_eq!(bend(0), -1.0);
+    assert!((bend(u32::MAX) - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn ump_ignores_other_message_types() {
+    // Utility and system real time packets
+    assert!(MidiMessage::from_ump([0x0000_0000, 0, 0, 0]).is_none());
+    assert!(MidiMessage::from_ump([0x10f8_0000, 0, 0, 0]).is_none());
+}
+
+#[test]
+fn pedal_hold_defers_the_release_while_sustained() {
+    let mut hold = PedalHold::default();
+    let sustained = ChannelPedals { sustain: true, sostenuto: false };
+    assert!(!hold.release(sustained));
+    assert!(!hold.pedals_changed(sustained));
+    assert!(hold.pedals_changed(ChannelPedals::default()));
+    // The deferred release only fires once
+    assert!(!hold.pedals_changed(ChannelPedals::default()));
+    assert!(hold.release(ChannelPedals::default()));
+}
+
+#[test]
+fn pedal_hold_sostenuto_needs_the_latch() {
+    let sostenuto = ChannelPedals { sustain: false, sostenuto: true };
+    assert!(PedalHold::default().release(sostenuto));
+
+    let mut hold = PedalHold { sostenuto_latched: true, ..PedalHold::default() };
+    assert!(!hold.release(sostenuto));
+    assert!(hold.pedals_changed(ChannelPedals::default()));
+    assert!(!hold.sostenuto_latched);
+}
+
+/// Counts the voices that are sounding and those of them that are releasing.
+fn voice_phases(synth: &PolySynth) -> (usize, usize) {
+    let voices = synth.envelopes().count();
+    let released = synth.envelopes().filter(|it| it.phase() == Some(ADSRPhase::Release)).count();
+    (voices, released)
+}
+
+fn send_midi(synth: &mut PolySynth, params: &SchoffhauzerSynthPluginParams, data: [u8; 3]) {
+    synth.handle_midi_message(MidiMessage::from_midi1(data).unwrap(), params);
+}
+
+fn set_param(params: &SchoffhauzerSynthPluginParams, info: &ParamInfo<'_>, value: f64) {
+    params.handle_param_value_event(&ParamValueEvent::new(0, info.id, Pckn::match_all(), value, Cookie::empty()));
+}
+
+/// Routes matrix slot 1 from `source` through `via` to `destination` at full `amount`.
+fn route(params: &SchoffhauzerSynthPluginParams, source: ModSource, via: ModSource, destination: &ParamInfo<'_>, amount: f64) {
+    let slot = &SchoffhauzerSynthPluginParams::MOD_MATRIX[0];
+    let index = SchoffhauzerSynthPluginParams::MOD_MATRIX_DESTINATIONS.iter().position(|info| info.id == destination.id);
+    set_param(params, slot.source, source.value() as f64);
+    set_param(params, slot.via, via.value() as f64);
+    set_param(params, slot.destination, (index.unwrap() + 1) as f64);
+    set_param(params, slot.amount, amount);
+}
+
+/// Both channels of a note at full velocity, after sending `midi` first.
+fn render_note(params: &SchoffhauzerSynthPluginParams, midi: &[[u8; 3]]) -> [Vec<f32>; 2] {
+    let mut synth = PolySynth::new(48000.0);
+    for &data in midi {
+        send_midi(&mut synth, params, data);
+    }
+    send_midi(&mut synth, params, [0x90, 60, 127]);
+    let (mut left, mut right) = (vec![0.0; 4800], vec![0.0; 4800]);
+    for (left, right) in left.chunks_mut(64).zip(right.chunks_mut(64)) {
+        synth.synth(left, right, params);
+    }
+    [left, right]
+}
+
+fn energy(samples: &[f32]) -> f32 {
+    samples.iter().map(|sample| sample * sample).sum()
+}
+
+#[test]
+fn mod_matrix_routes_a_source_to_its_destination() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let [unrouted, _] = render_note(&params, &[]);
+    route(&params, ModSource::Velocity, ModSource::Off, SchoffhauzerSynthPluginParams::VOLUME, -0.5);
+    let [routed, _] = render_note(&params, &[]);
+    // Half the range down from 0dB at full velocity is -36dB
+    let ratio = energy(&routed) / energy(&unrouted);
+    assert!((ratio.log10() * 10.0 + 36.0).abs() < 0.1, "{ratio}");
+}
+
+#[test]
+fn mod_matrix_via_scales_the_source() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    route(&params, ModSource::Velocity, ModSource::Off, SchoffhauzerSynthPluginParams::VOLUME, -0.5);
+    let [full, _] = render_note(&params, &[]);
+    route(&params, ModSource::Velocity, ModSource::ModWheel, SchoffhauzerSynthPluginParams::VOLUME, -0.5);
+    let [closed, _] = render_note(&params, &[[0xb0, CC_MOD_WHEEL, 0]]);
+    let [open, _] = render_note(&params, &[[0xb0, CC_MOD_WHEEL, 127]]);
+    route(&params, ModSource::Off, ModSource::Off, SchoffhauzerSynthPluginParams::VOLUME, 0.0);
+    assert_eq!(closed, render_note(&params, &[])[0]);
+    assert_eq!(open, full);
+}
+
+#[test]
+fn mod_matrix_narrows_each_voice() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    set_param(&params, SchoffhauzerSynthPluginParams::UNISON_VOICES, 4.0);
+    let [left, right] = render_note(&params, &[]);
+    assert_ne!(left, right, "unison spread is stereo");
+    route(&params, ModSource::Velocity, ModSource::Off, SchoffhauzerSynthPluginParams::WIDTH, -1.0);
+    let [left, right] = render_note(&params, &[]);
+    assert_eq!(left, right);
+}
+
+/// The left channel of a held note, with the volume dropped to its minimum after `SETTLE` samples if `step`.
+fn render_volume_step(step: bool) -> Vec<f32> {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let mut synth = PolySynth::new(48000.0);
+    send_midi(&mut synth, &params, [0x90, 60, 100]);
+    let (mut left, mut right) = (vec![0.0; 4 * SETTLE], vec![0.0; 4 * SETTLE]);
+    for (i, (left, right)) in left.chunks_mut(64).zip(right.chunks_mut(64)).enumerate() {
+        if step && i * 64 == SETTLE {
+            set_param(&params, SchoffhauzerSynthPluginParams::VOLUME, -60.0);
+        }
+        synth.synth(left, right, &params);
+    }
+    left
+}
+
+#[test]
+fn volume_steps_ramp_across_samples() {
+    let (stepped, steady) = (render_volume_step(true), render_volume_step(false));
+    assert_eq!(stepped[..SETTLE], steady[..SETTLE]);
+    // Everything but the volume is the same, so the ratio of the renders is the smoothed gain
+    let gains: Vec<f32> = stepped[SETTLE..]
+        .iter()
+        .zip(&steady[SETTLE..])
+        .filter(|(_, steady)| steady.abs() > 1e-2)
+        .map(|(stepped, steady)| stepped / steady)
+        .collect();
+    assert!(gains[0] > 0.9, "jumped to {}", gains[0]);
+    assert!(gains.windows(2).all(|pair| pair[1] <= pair[0] + 1e-4), "not a ramp");
+    assert!(*gains.last().unwrap() < 0.01, "ended at {}", gains.last().unwrap());
+}
+
+/// Samples from the note off until the voice has ended, in blocks of 64.
+fn release_length(params: &SchoffhauzerSynthPluginParams, release_velocity: u8) -> usize {
+    let mut synth = PolySynth::new(48000.0);
+    let (mut left, mut right) = ([0.0; 64], [0.0; 64]);
+    send_midi(&mut synth, params, [0x90, 60, 100]);
+    synth.synth(&mut left, &mut right, params);
+    send_midi(&mut synth, params, [0x80, 60, release_velocity]);
+    let mut length = 0;
+    while synth.envelopes().count() > 0 {
+        synth.synth(&mut left, &mut right, params);
+        length += left.len();
+    }
+    length
+}
+
+#[test]
+fn release_velocity_shortens_the_release() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let release = release_length(&params, 0);
+    assert_eq!(release_length(&params, 127), release, "ignored at the default amount");
+
+    set_param(&params, SchoffhauzerSynthPluginParams::VELOCITY_RELEASE, 1.0);
+    assert_eq!(release_length(&params, 0), release);
+    assert!(release_length(&params, 64) < release);
+    assert!(release_length(&params, 127) < release_length(&params, 64));
+}
+
+#[test]
+fn sustain_holds_released_notes_until_lifted() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let mut synth = PolySynth::new(48000.0);
+    send_midi(&mut synth, &params, [0x90, 60, 100]);
+    send_midi(&mut synth, &params, [0xb0, CC_SUSTAIN, 127]);
+    send_midi(&mut synth, &params, [0x80, 60, 64]);
+    // Notes played while the pedal is down are held as well
+    send_midi(&mut synth, &params, [0x90, 62, 100]);
+    send_midi(&mut synth, &params, [0x80, 62, 64]);
+    assert_eq!(voice_phases(&synth), (2, 0));
+
+    send_midi(&mut synth, &params, [0xb0, CC_SUSTAIN, 0]);
+    assert_eq!(voice_phases(&synth), (2, 2));
+}
+
+#[test]
+fn sostenuto_holds_only_the_notes_down_when_pressed() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let mut synth = PolySynth::new(48000.0);
+    send_midi(&mut synth, &params, [0x90, 60, 100]);
+    send_midi(&mut synth, &params, [0xb0, CC_SOSTENUTO, 127]);
+    send_midi(&mut synth, &params, [0x90, 62, 100]);
+    send_midi(&mut synth, &params, [0x80, 60, 64]);
+    send_midi(&mut synth, &params, [0x80, 62, 64]);
+    assert_eq!(voice_phases(&synth), (2, 1));
+
+    send_midi(&mut synth, &params, [0xb0, CC_SOSTENUTO, 0]);
+    assert_eq!(voice_phases(&synth), (2, 2));
+}
+
+#[test]
+fn pedals_only_hold_their_own_channel() {
+    let params = SchoffhauzerSynthPluginParams::default();
+    let mut synth = PolySynth::new(48000.0);
+    send_midi(&mut synth, &params, [0xb1, CC_SUSTAIN, 127]);
+    send_midi(&mut synth, &params, [0x90, 60, 100]);
+    send_midi(&mut synth, &params, [0x80, 60, 64]);
+    assert_eq!(voice_phases(&synth), (1, 1));
+}
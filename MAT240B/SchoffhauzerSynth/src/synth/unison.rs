@@ -0,0 +1,109 @@
+use crate::synth::synth::{Synth, Waveform};
+use crate::utils::pan;
+
+/// Stacked copies of [`Synth`] for one note, detuned and spread across the stereo field.
+pub struct Unison {
+    synths: [Synth; Unison::MAX_VOICES],
+    count: usize,
+    /// Frequency multiplier of each copy
+    detune_ratios: [f32; Unison::MAX_VOICES],
+    /// `(left, right)` gain of each copy, including the loudness compensation
+    gains: [(f32, f32); Unison::MAX_VOICES],
+}
+
+impl Unison {
+    pub const MAX_VOICES: usize = 8;
+
+    /// Every copy but the first starts at a random phase, derived from `seed` so renders are repeatable.
+    pub fn new(sample_rate: f32, freq: f32, seed: u32) -> Self {
+        let mut rng = seed.wrapping_mul(0x9e37_79b9) | 1;
+        let synths = std::array::from_fn(|i| {
+            let mut synth = Synth::new(sample_rate, freq);
+            if i > 0 {
+                // xorshift32
+                rng ^= rng << 13;
+                rng ^= rng >> 17;
+                rng ^= rng << 5;
+                synth.set_phase(rng as f32 / u32::MAX as f32 * 2.0 - 1.0);
+            }
+            synth
+        });
+        Self {
+            synths,
+            count: 1,
+            detune_ratios: [1.0; Self::MAX_VOICES],
+            gains: [(1.0, 1.0); Self::MAX_VOICES],
+        }
+    }
+
+    /// Spreads `count` copies evenly over `-detune..=detune` cents, call [`Unison::set_pan`] after.
+    pub fn configure(&mut self, count: usize, detune: f32) {
+        self.count = count.clamp(1, Self::MAX_VOICES);
+        for i in 0..self.count {
+            self.detune_ratios[i] = f32::powf(2.0, self.offset(i) * detune / 1200.0);
+        }
+    }
+
+    /// Spreads the copies evenly over `pan - spread..=pan + spread`.
+    pub fn set_pan(&mut self, pan: f32, spread: f32) {
+        // Keeps the loudness of uncorrelated copies roughly constant
+        let compensation = 1.0 / (self.count as f32).sqrt();
+        for i in 0..self.count {
+            let (left, right) = pan::balance(pan + self.offset(i) * spread);
+            self.gains[i] = (left * compensation, right * compensation);
+        }
+    }
+
+    /// Position of copy `i` in `-1.0..=1.0`
+    fn offset(&self, i: usize) -> f32 {
+        if self.count == 1 {
+            0.0
+        } else {
+            i as f32 / (self.count - 1) as f32 * 2.0 - 1.0
+        }
+    }
+
+    pub fn set_freq(&mut self, freq: f32) {
+        for (synth, ratio) in self.synths.iter_mut().zip(self.detune_ratios).take(self.count) {
+            synth.freq = freq * ratio;
+        }
+    }
+
+    pub fn set_hf_rolloff(&mut self, hf_rolloff: f32) {
+        for synth in &mut self.synths[..self.count] {
+            synth.hf_rolloff = hf_rolloff;
+        }
+    }
+
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        for synth in &mut self.synths[..self.count] {
+            synth.set_waveform(waveform);
+        }
+    }
+
+    pub fn set_pulse_width(&mut self, pulse_width: f32) {
+        for synth in &mut self.synths[..self.count] {
+            synth.pulse_width = pulse_width;
+        }
+    }
+
+    /// Hard syncs every copy to the copy of `master` with the same detune, call right after `master.synth()`.
+    pub fn sync_to(&mut self, master: &Unison) {
+        for (synth, master) in self.synths.iter_mut().zip(&master.synths).take(self.count) {
+            if let Some(fraction) = master.wrap() {
+                synth.sync(fraction);
+            }
+        }
+    }
+
+    /// Returns the next `(left, right)` sample.
+    pub fn synth(&mut self) -> (f32, f32) {
+        let (mut left, mut right) = (0.0, 0.0);
+        for (synth, (left_gain, right_gain)) in self.synths.iter_mut().zip(self.gains).take(self.count) {
+            let sample = synth.synth();
+            left += sample * left_gain;
+            right += sample * right_gain;
+        }
+        (left, right)
+    }
+}
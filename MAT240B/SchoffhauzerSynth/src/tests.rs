@@ -0,0 +1,71 @@
+//! Tests of the saved state format.
+
+use crate::params::SchoffhauzerSynthPluginParams;
+use crate::save_state::SchoffhauzerSynthPluginState;
+
+fn load(json: &str) -> SchoffhauzerSynthPluginParams {
+    let params = SchoffhauzerSynthPluginParams::default();
+    SchoffhauzerSynthPluginState::from_reader(json.as_bytes()).unwrap().apply_to(&params);
+    params
+}
+
+#[test]
+fn baseline_state_loads() {
+    // Everything the first version of the plugin saved
+    let params = load(
+        r#"{
+            "volume": -6.0,
+            "adsr": {
+                "attack_duration": 0.2,
+                "attack_power": 1.0,
+                "decay_duration": 0.4,
+                "decay_power": 1.5,
+                "sustain": 0.8,
+                "release_duration": 1.2,
+                "release_power": 2.0
+            },
+            "hf_rolloff": 0.4
+        }"#,
+    );
+    assert_eq!(params.get_volume().value.db(), -6.0);
+    let adsr = params.get_adsr();
+    assert_eq!(adsr.sustain.value, 0.8);
+    assert_eq!(adsr.release_duration.value, 1.2);
+    let oscillators = params.get_oscillators();
+    assert_eq!(oscillators[0].hf_rolloff.value, 0.4);
+    // Parameters added since keep their defaults
+    let info = SchoffhauzerSynthPluginParams::OSCILLATORS[0].level;
+    assert_eq!(oscillators[0].level.value, info.default_value as f32);
+}
+
+#[test]
+fn missing_nested_fields_keep_their_defaults() {
+    let params = load(r#"{"voice_lfo": {"rate": 5.0}, "oscillators": [{"level": 0.5}, {"fine": 10.0}]}"#);
+    type Params = SchoffhauzerSynthPluginParams;
+    let voice_lfo = params.get_voice_lfo();
+    assert_eq!(voice_lfo.rate.value, 5.0);
+    assert_eq!(voice_lfo.retrigger.value, Params::VOICE_LFO.retrigger.default_value as f32);
+    assert_eq!(voice_lfo.amount.value, Params::VOICE_LFO.amount.default_value as f32);
+
+    let oscillators = params.get_oscillators();
+    assert_eq!(oscillators[0].level.value, 0.5);
+    assert_eq!(oscillators[0].hf_rolloff.value, Params::HF_ROLLOFF.default_value as f32);
+    assert_eq!(oscillators[1].fine.value, 10.0);
+    assert_eq!(oscillators[1].hf_rolloff.value, Params::OSCILLATORS[1].hf_rolloff.default_value as f32);
+    assert_eq!(oscillators[2].level.value, Params::OSCILLATORS[2].level.default_value as f32);
+}
+
+#[test]
+fn saved_state_round_trips() {
+    let saved = SchoffhauzerSynthPluginParams::default();
+    saved.oscillators.update(|oscillators| oscillators[0].hf_rolloff.value = 0.25);
+    saved.voice_lfo.update(|lfo| lfo.rate.value = 7.0);
+    let json = serde_json::to_string(&SchoffhauzerSynthPluginState::from_params(&saved)).unwrap();
+    // Only the nested rolloff is written
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert!(value.get("hf_rolloff").is_none());
+
+    let params = load(&json);
+    assert_eq!(params.get_oscillators()[0].hf_rolloff.value, 0.25);
+    assert_eq!(params.get_voice_lfo().rate.value, 7.0);
+}
@@ -0,0 +1,75 @@
+//! In tests and with the `alloc-guard` feature, a global allocator that reports memory allocated or freed
+//! inside [`forbid_alloc`], to catch real-time violations on the audio thread early.
+//! Otherwise the crate leaves the allocator alone, so it doesn't replace it for every dependent,
+//! and [`forbid_alloc`] is free.
+
+#[cfg(any(test, feature = "alloc-guard"))]
+mod guarded {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    thread_local! {
+        pub static FORBIDDEN: Cell<bool> = const { Cell::new(false) };
+    }
+
+    pub struct GuardedAllocator;
+
+    fn check(action: &str) {
+        if FORBIDDEN.try_with(|forbidden| forbidden.replace(false)).unwrap_or(false) {
+            // The flag is cleared first so reporting is allowed to allocate.
+            // Inside the plugin a panic would unwind through the host's FFI frames, so it aborts instead.
+            if cfg!(test) {
+                panic!("{action} on the audio thread");
+            }
+            eprintln!("{action} on the audio thread");
+            std::process::abort();
+        }
+    }
+
+    unsafe impl GlobalAlloc for GuardedAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            check("allocation");
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            check("allocation");
+            unsafe { System.alloc_zeroed(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            check("deallocation");
+            unsafe { System.dealloc(ptr, layout) }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            check("reallocation");
+            unsafe { System.realloc(ptr, layout, new_size) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: GuardedAllocator = GuardedAllocator;
+
+    /// Restores the previous state on drop, so nesting and unwinding both work.
+    pub struct Guard(bool);
+
+    impl Guard {
+        pub fn new() -> Self {
+            Self(FORBIDDEN.with(|forbidden| forbidden.replace(true)))
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            FORBIDDEN.with(|forbidden| forbidden.set(self.0));
+        }
+    }
+}
+
+/// Runs `f`, failing the test or aborting with the `alloc-guard` feature if it touches the heap.
+pub fn forbid_alloc<R>(f: impl FnOnce() -> R) -> R {
+    #[cfg(any(test, feature = "alloc-guard"))]
+    let _guard = guarded::Guard::new();
+    f()
+}
@@ -0,0 +1,88 @@
+use crate::utils::db::DB;
+use crate::utils::envelope::ADSR;
+use crate::utils::modulated::Modulated;
+use std::marker::PhantomData;
+use std::mem::{MaybeUninit, align_of, size_of};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Types made of nothing but `f32`s, so they can be stored one [`AtomicU32`] per field.
+///
+/// # Safety
+///
+/// The type must have the same layout as `[f32; N]` for some `N`: every byte belongs to an `f32` field,
+/// with no padding and no fields of other types. The default `repr(Rust)` layout is unspecified,
+/// so structs need `#[repr(C)]` with only [`F32Fields`] fields, or `#[repr(transparent)]` around one.
+/// Implementations are followed by a compile-time check of the size, which catches a field of another type.
+pub unsafe trait F32Fields: Copy {}
+
+unsafe impl F32Fields for f32 {}
+
+unsafe impl F32Fields for DB<f32> {}
+const _: () = assert!(size_of::<DB<f32>>() == size_of::<f32>());
+
+unsafe impl<T: F32Fields> F32Fields for Modulated<T> {}
+const _: () = assert!(size_of::<Modulated<f32>>() == 2 * size_of::<f32>());
+
+unsafe impl<T: F32Fields> F32Fields for ADSR<T> {}
+const _: () = assert!(size_of::<ADSR<f32>>() == 7 * size_of::<f32>());
+
+unsafe impl<T: F32Fields, const N: usize> F32Fields for [T; N] {}
+
+/// Parameter storage the audio thread can read and write without ever taking a lock.
+///
+/// Each `f32` field is its own atomic, so a reader may see one field from before a concurrent write and another
+/// from after it, but never a torn field. Writers only touch the fields they change, so writes to different fields
+/// from different threads never undo each other.
+pub struct AtomicParam<T: F32Fields> {
+    fields: Box<[AtomicU32]>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: F32Fields> AtomicParam<T> {
+    const FIELDS: usize = {
+        assert!(align_of::<T>() == align_of::<f32>());
+        assert!(size_of::<T>().is_multiple_of(size_of::<f32>()));
+        size_of::<T>() / size_of::<f32>()
+    };
+
+    pub fn new(value: T) -> Self {
+        Self {
+            fields: Self::bits(&value).iter().map(|&bits| AtomicU32::new(bits)).collect(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn load(&self) -> T {
+        let mut value = MaybeUninit::<T>::uninit();
+        let bits = value.as_mut_ptr().cast::<u32>();
+        for (i, field) in self.fields.iter().enumerate() {
+            // SAFETY: `T` is exactly `FIELDS` `f32`s, see `F32Fields`
+            unsafe { bits.add(i).write(field.load(Ordering::Relaxed)) };
+        }
+        // SAFETY: Every field was written above, and any bits are a valid `f32`
+        unsafe { value.assume_init() }
+    }
+
+    pub fn store(&self, value: T) {
+        for (field, &bits) in self.fields.iter().zip(Self::bits(&value)) {
+            field.store(bits, Ordering::Relaxed);
+        }
+    }
+
+    /// Applies `f` to the current value and stores the fields it changed.
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        let before = self.load();
+        let mut after = before;
+        f(&mut after);
+        for ((field, before), &after) in self.fields.iter().zip(Self::bits(&before)).zip(Self::bits(&after)) {
+            if *before != after {
+                field.store(after, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn bits(value: &T) -> &[u32] {
+        // SAFETY: `T` is exactly `FIELDS` `f32`s, see `F32Fields`
+        unsafe { std::slice::from_raw_parts((value as *const T).cast::<u32>(), Self::FIELDS) }
+    }
+}
@@ -5,6 +5,7 @@ use std::fmt::Debug;
 
 #[derive_aliases::derive(..Copy, Default, ..Ord, Debug, Display, From, ..NumOpsAssign, ..SerDe)]
 #[display("{_0} dB")]
+#[repr(transparent)]
 pub struct DB<F: Float + Debug + 'static>(pub F)
 where
     f32: AsPrimitive<F>;
@@ -14,7 +15,7 @@ where
     f32: AsPrimitive<F>,
 {
     pub fn from_linear(linear: F) -> Self {
-        Self(linear.log10() / 20.0.as_())
+        Self(linear.log10() * 20.0.as_())
     }
 
     pub fn db(self) -> F {
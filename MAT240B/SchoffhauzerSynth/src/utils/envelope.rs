@@ -5,6 +5,7 @@ use repetitive::repetitive;
 #[derive_aliases::derive(..Copy, Debug, derive_more::Display, Default, ..SerDe)]
 #[display(bound(T: Debug))]
 #[display("{self:?}")]
+#[repr(C)]
 pub struct ADSR<T> {
     pub attack_duration: T,
     pub attack_power: T,
@@ -55,6 +56,21 @@ impl<T> ADSR<T> {
     }
 }
 
+impl ADSR<f32> {
+    /// Level `progress` of the way through `phase`, which started at `start_level`.
+    /// The attack always starts from silence.
+    pub fn level(&self, phase: ADSRPhase, start_level: f32, progress: f32) -> f32 {
+        let range = match phase {
+            ADSRPhase::Attack => 0.0..=1.0,
+            ADSRPhase::Decay => start_level..=self.sustain,
+            ADSRPhase::Sustain => return self.sustain,
+            ADSRPhase::Release => start_level..=0.0,
+        };
+        let power = *self.power(phase).unwrap();
+        lerp(range, progress.powf(power))
+    }
+}
+
 impl<T: Copy> ADSR<Option<T>> {
     pub fn _unwrap_or(self, default: ADSR<T>) -> ADSR<T> {
         self.map2(&default, |a, b| a.unwrap_or(*b))
@@ -99,21 +115,10 @@ impl ADSRInstance {
     }
 
     pub fn current_level(&self) -> f32 {
-        if self.phase == Some(ADSRPhase::Sustain) {
-            return self.adsr.sustain;
-        }
-        if self.phase == None {
-            return 0.0;
+        match self.phase {
+            Some(phase) => self.adsr.level(phase, self.start_level, self.progress),
+            None => 0.0,
         }
-
-        let range = match self.phase {
-            Some(ADSRPhase::Attack) => 0.0..=1.0,
-            Some(ADSRPhase::Decay) => self.start_level..=self.adsr.sustain,
-            Some(ADSRPhase::Release) => self.start_level..=0.0,
-            _ => unreachable!(),
-        };
-        let power = *self.adsr.power(self.phase.unwrap()).unwrap();
-        lerp(range, self.progress.powf(power))
     }
 
     pub fn advance(&mut self, delta: f32) {
@@ -154,6 +159,15 @@ impl ADSRInstance {
         };
     }
 
+    pub fn phase(&self) -> Option<ADSRPhase> {
+        self.phase
+    }
+
+    /// Fraction of the current phase that has passed, meaningless while sustaining.
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
     pub fn force_end(&mut self) {
         self.phase = None;
     }
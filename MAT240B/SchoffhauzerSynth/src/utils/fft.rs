@@ -0,0 +1,60 @@
+use std::f32::consts::PI;
+
+/// In-place radix-2 FFT of the complex signal `real + i * imag`, whose length must be a power of two.
+pub fn fft(real: &mut [f32], imag: &mut [f32]) {
+    let len = real.len();
+    assert_eq!(len, imag.len(), "real and imaginary parts must have the same length");
+    assert!(len.is_power_of_two(), "length must be a power of two");
+
+    // Bit reversal permutation
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            real.swap(i, j);
+            imag.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= len {
+        let step = -2.0 * PI / size as f32;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let (sin, cos) = (step * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + size / 2);
+                let twiddled_real = real[b] * cos - imag[b] * sin;
+                let twiddled_imag = real[b] * sin + imag[b] * cos;
+                real[b] = real[a] - twiddled_real;
+                imag[b] = imag[a] - twiddled_imag;
+                real[a] += twiddled_real;
+                imag[a] += twiddled_imag;
+            }
+        }
+        size *= 2;
+    }
+}
+
+/// Magnitude of each bin up to Nyquist, of `samples` under a Hann window and normalized so a full scale sine peaks near `1.0`.
+pub fn magnitude_spectrum(samples: &[f32]) -> Vec<f32> {
+    let len = samples.len();
+    let mut real: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| sample * (0.5 - 0.5 * f32::cos(2.0 * PI * i as f32 / len as f32)))
+        .collect();
+    let mut imag = vec![0.0; len];
+    fft(&mut real, &mut imag);
+    // The Hann window halves the amplitude and the two sided spectrum halves it again
+    let scale = 4.0 / len as f32;
+    real.iter()
+        .zip(&imag)
+        .take(len / 2)
+        .map(|(real, imag)| real.hypot(*imag) * scale)
+        .collect()
+}
@@ -1,28 +1,23 @@
-use core::range::Step;
 use num_traits::Num;
-use std::ops::{Range, RangeInclusive};
+use std::ops::RangeInclusive;
 
 pub mod db;
 pub mod midi_note;
 pub mod modulated;
 pub mod envelope;
 pub mod fallback;
+pub mod param_enum;
+pub mod velocity;
+pub mod pan;
+pub mod voice_pool;
+pub mod alloc_guard;
+pub mod ring_buffer;
+pub mod fft;
+pub mod atomic_param;
+pub mod smoother;
 
-pub trait Single<T> {
-    fn single(value: T) -> Self;
-}
-
-impl<T: Copy + Step> Single<T> for RangeInclusive<T> {
-    fn single(value: T) -> Self {
-        value..=value
-    }
-}
-
-impl<T: Num + Copy + Step> Single<T> for Range<T> {
-    fn single(value: T) -> Self {
-        value..(value + T::one())
-    }
-}
+#[cfg(test)]
+mod tests;
 
 pub fn lerp<T: Num + Copy>(range: impl Into<RangeInclusive<T>>, t: T) -> T {
     let range = range.into();
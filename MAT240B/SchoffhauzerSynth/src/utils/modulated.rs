@@ -6,6 +6,7 @@ use std::ops::Add;
 
 #[derive_aliases::derive(..Copy, Debug, Display, ..SerDe, Default)]
 #[display("({value} + {modulation})")]
+#[repr(C)]
 pub struct Modulated<T> {
     pub value: T,
     pub modulation: T,
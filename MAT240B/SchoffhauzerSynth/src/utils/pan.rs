@@ -0,0 +1,17 @@
+/// Balance law: `0.0` is center and leaves both channels at unity gain,
+/// `-1.0`/`1.0` silence the opposite channel. Returns the `(left, right)` gains.
+pub fn balance(pan: f32) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0);
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+/// Scales the side signal of a stereo sample, `0.0` collapses it to mono and `1.0` leaves it untouched.
+/// Linear, so widening each voice is the same as widening their mix.
+pub fn width(left: f32, right: f32, width: f32) -> (f32, f32) {
+    if width == 1.0 {
+        return (left, right);
+    }
+    let mid = (left + right) * 0.5;
+    let side = (left - right) * 0.5 * width;
+    (mid + side, mid - side)
+}
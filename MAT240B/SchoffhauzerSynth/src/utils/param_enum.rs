@@ -0,0 +1,26 @@
+use std::fmt::Display;
+
+/// An enum exposed as a stepped CLAP parameter, the parameter value is the index into [`ParamEnum::ALL`].
+pub trait ParamEnum: Copy + Eq + Display + 'static {
+    const ALL: &'static [Self];
+
+    /// Maximum parameter value, for the `ParamInfo` range.
+    const MAX_VALUE: f64 = (Self::ALL.len() - 1) as f64;
+
+    /// Maps a parameter value to a variant, clamping out of range values.
+    fn from_value(value: f32) -> Self {
+        Self::ALL[(value.round().max(0.0) as usize).min(Self::ALL.len() - 1)]
+    }
+
+    fn value(self) -> f32 {
+        Self::ALL.iter().position(|&it| it == self).unwrap() as f32
+    }
+
+    /// Accepts either the displayed name or the raw index.
+    fn text_to_value(text: &str) -> Option<f64> {
+        match Self::ALL.iter().find(|it| it.to_string().eq_ignore_ascii_case(text.trim())) {
+            Some(it) => Some(it.value() as f64),
+            None => text.trim().parse().ok(),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Fixed-capacity lock-free queue for handing values between the audio thread and the GUI,
+/// preallocated so neither side ever allocates or blocks.
+///
+/// Values are pushed through a [`Producer`] and popped through a [`Consumer`],
+/// at most one of each exists at any time so there is only ever one thread on either end.
+/// A full queue rejects new values instead of overwriting old ones.
+pub struct RingBuffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Total number of values popped, wrapping
+    head: AtomicUsize,
+    /// Total number of values pushed, wrapping
+    tail: AtomicUsize,
+    producer_claimed: AtomicBool,
+    consumer_claimed: AtomicBool,
+}
+
+// Safety: only the one `Producer` writes slots and only the one `Consumer` reads them,
+// a slot is only read after the push that wrote it is published through `tail`,
+// and only written again after the pop that read it is published through `head`.
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+impl<T: Copy> RingBuffer<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            producer_claimed: AtomicBool::new(false),
+            consumer_claimed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+    }
+
+    /// The pushing end, `None` while another thread holds it.
+    pub fn producer(&self) -> Option<Producer<'_, T>> {
+        claim(&self.producer_claimed).then_some(Producer { buffer: self })
+    }
+
+    /// The popping end, `None` while another thread holds it.
+    pub fn consumer(&self) -> Option<Consumer<'_, T>> {
+        claim(&self.consumer_claimed).then_some(Consumer { buffer: self })
+    }
+}
+
+/// Acquiring pairs with the release of the previous holder, so a new holder on another thread sees its last index.
+fn claim(claimed: &AtomicBool) -> bool {
+    claimed.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok()
+}
+
+/// The only handle that pushes to a [`RingBuffer`], released when dropped.
+pub struct Producer<'a, T> {
+    buffer: &'a RingBuffer<T>,
+}
+
+impl<T: Copy> Producer<'_, T> {
+    /// At least this many values fit before the queue is full.
+    pub fn free_capacity(&self) -> usize {
+        let buffer = self.buffer;
+        let used = buffer.tail.load(Ordering::Relaxed).wrapping_sub(buffer.head.load(Ordering::Acquire));
+        buffer.capacity() - used
+    }
+
+    /// Hands `value` back if the queue is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let buffer = self.buffer;
+        let tail = buffer.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(buffer.head.load(Ordering::Acquire)) >= buffer.capacity() {
+            return Err(value);
+        }
+        // Safety: the slot is outside of the readable range until `tail` is published,
+        // and this is the only producer
+        unsafe { (*buffer.slots[tail % buffer.capacity()].get()).write(value) };
+        buffer.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T> Drop for Producer<'_, T> {
+    fn drop(&mut self) {
+        self.buffer.producer_claimed.store(false, Ordering::Release);
+    }
+}
+
+/// The only handle that pops from a [`RingBuffer`], released when dropped.
+pub struct Consumer<'a, T> {
+    buffer: &'a RingBuffer<T>,
+}
+
+impl<T: Copy> Consumer<'_, T> {
+    pub fn pop(&mut self) -> Option<T> {
+        let buffer = self.buffer;
+        let head = buffer.head.load(Ordering::Relaxed);
+        if head == buffer.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        // Safety: the slot was initialized by a push published through `tail`,
+        // and this is the only consumer
+        let value = unsafe { (*buffer.slots[head % buffer.capacity()].get()).assume_init() };
+        buffer.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Pops values until the queue is empty, including values pushed while draining.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.pop())
+    }
+}
+
+impl<T> Drop for Consumer<'_, T> {
+    fn drop(&mut self) {
+        self.buffer.consumer_claimed.store(false, Ordering::Release);
+    }
+}
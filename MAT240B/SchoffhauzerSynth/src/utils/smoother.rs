@@ -0,0 +1,34 @@
+/// One-pole lowpass that glides towards each new target instead of jumping to it, so changing a continuous
+/// parameter doesn't step the output once per block.
+#[derive_aliases::derive(..Copy, Debug)]
+pub struct Smoother {
+    /// `None` until the first target, which is taken as is
+    value: Option<f32>,
+    coefficient: f32,
+}
+
+impl Smoother {
+    /// Closer than this the value snaps to the target, which also keeps it out of denormals
+    const EPSILON: f32 = 1e-6;
+
+    /// `time` in seconds is how long it takes to cover about 63% of a jump, `0.0` follows targets instantly.
+    pub fn new(time: f32, sample_rate: f32) -> Self {
+        let coefficient = if time > 0.0 { f32::exp(-1.0 / (time * sample_rate)) } else { 0.0 };
+        Self { value: None, coefficient }
+    }
+
+    /// Advances one sample towards `target` and returns the smoothed value.
+    pub fn next(&mut self, target: f32) -> f32 {
+        let value = match self.value {
+            Some(value) if (value - target).abs() > Self::EPSILON => target + (value - target) * self.coefficient,
+            _ => target,
+        };
+        self.value = Some(value);
+        value
+    }
+
+    /// Whether the value has reached `target`, after which [`Smoother::next`] returns it unchanged.
+    pub fn is_settled(&self, target: f32) -> bool {
+        self.value == Some(target)
+    }
+}
@@ -0,0 +1,162 @@
+//! Tests of the utilities the synth builds on.
+
+use crate::utils::alloc_guard::forbid_alloc;
+use crate::utils::db::DB;
+use crate::utils::param_enum::ParamEnum;
+use crate::utils::ring_buffer::RingBuffer;
+use crate::utils::velocity::{self, VelocityCurve};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn velocity_curves_are_unity_at_full_velocity() {
+    for &curve in [VelocityCurve::Linear, VelocityCurve::Exponential, VelocityCurve::Fixed].iter() {
+        assert_eq!(curve.gain(1.0), 1.0, "{curve}");
+    }
+}
+
+#[test]
+fn linear_velocity_curve_is_the_velocity() {
+    for velocity in [0.0, 0.25, 0.5, 0.75] {
+        assert_eq!(VelocityCurve::Linear.gain(velocity), velocity);
+    }
+}
+
+#[test]
+fn exponential_velocity_curve_is_linear_in_db() {
+    let range = VelocityCurve::EXPONENTIAL_RANGE.db();
+    for velocity in [0.0f32, 0.25, 0.5, 0.75] {
+        let db = 20.0 * VelocityCurve::Exponential.gain(velocity).log10();
+        assert!((db - (velocity - 1.0) * range).abs() < 1e-3, "{velocity}: {db} dB");
+    }
+}
+
+#[test]
+fn fixed_velocity_curve_ignores_velocity() {
+    for velocity in [0.0, 0.5, 1.0] {
+        assert_eq!(VelocityCurve::Fixed.gain(velocity), 1.0);
+    }
+}
+
+#[test]
+fn velocity_hf_rolloff_offset_spans_the_amount() {
+    for amount in [-1.0, -0.5, 0.0, 0.5, 1.0] {
+        assert_eq!(velocity::hf_rolloff_offset(amount, 1.0), 0.0);
+        assert_eq!(velocity::hf_rolloff_offset(amount, 0.0), -amount);
+        assert_eq!(velocity::hf_rolloff_offset(amount, 0.5), -0.5 * amount);
+    }
+}
+
+#[test]
+fn velocity_release_scale_follows_the_curve() {
+    for &curve in VelocityCurve::ALL {
+        assert_eq!(velocity::release_scale(curve, 1.0, 0.0), 1.0, "{curve}");
+        assert_eq!(velocity::release_scale(curve, 0.0, 1.0), 1.0, "{curve}");
+        let fastest = velocity::release_scale(curve, 1.0, 1.0);
+        assert!((fastest - curve.gain(0.0)).abs() < 1e-6, "{curve}: {fastest}");
+    }
+    assert_eq!(velocity::release_scale(VelocityCurve::Linear, 0.5, 1.0), 0.5);
+}
+
+#[test]
+fn db_from_linear_is_twenty_log10() {
+    assert_eq!(DB::from_linear(1.0f32).db(), 0.0);
+    assert!((DB::from_linear(10.0f32).db() - 20.0).abs() < 1e-5);
+    assert!((DB::from_linear(0.5f32).db() + 6.0206).abs() < 1e-3);
+}
+
+#[test]
+fn db_from_linear_inverts_linear() {
+    for db in [-60.0f32, -6.0, 0.0, 12.0] {
+        assert!((DB::from_linear(DB(db).linear()).db() - db).abs() < 1e-3, "{db} dB");
+    }
+}
+
+#[test]
+#[should_panic(expected = "allocation on the audio thread")]
+fn forbid_alloc_catches_allocations() {
+    forbid_alloc(|| std::hint::black_box(Vec::<u8>::with_capacity(16)));
+}
+
+#[test]
+fn forbid_alloc_allows_allocations_afterwards() {
+    forbid_alloc(|| ());
+    let _ = std::hint::black_box(Vec::<u8>::with_capacity(16));
+}
+
+#[test]
+fn ring_buffer_is_empty_until_pushed_and_rejects_when_full() {
+    let buffer = RingBuffer::with_capacity(3);
+    let mut producer = buffer.producer().unwrap();
+    let mut consumer = buffer.consumer().unwrap();
+    assert!(buffer.is_empty());
+    assert_eq!(consumer.pop(), None);
+    assert_eq!(producer.free_capacity(), 3);
+    for value in 0..3 {
+        assert_eq!(producer.push(value), Ok(()));
+    }
+    assert!(!buffer.is_empty());
+    assert_eq!(producer.free_capacity(), 0);
+    assert_eq!(producer.push(3), Err(3));
+    assert_eq!(consumer.pop(), Some(0));
+    assert_eq!(producer.free_capacity(), 1);
+    assert_eq!(consumer.drain().collect::<Vec<_>>(), [1, 2]);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn ring_buffer_keeps_order_across_wraparound() {
+    let buffer = RingBuffer::with_capacity(4);
+    let mut producer = buffer.producer().unwrap();
+    let mut consumer = buffer.consumer().unwrap();
+    for value in 0..3 {
+        producer.push(value).unwrap();
+    }
+    // Three values stay in flight while the indices wrap around many times
+    for value in 3..40 {
+        producer.push(value).unwrap();
+        assert_eq!(consumer.pop(), Some(value - 3));
+    }
+    assert_eq!(consumer.drain().collect::<Vec<_>>(), [37, 38, 39]);
+}
+
+#[test]
+fn ring_buffer_hands_out_one_producer_and_one_consumer() {
+    let buffer = RingBuffer::<i32>::with_capacity(1);
+    let producer = buffer.producer().unwrap();
+    let consumer = buffer.consumer().unwrap();
+    assert!(buffer.producer().is_none());
+    assert!(buffer.consumer().is_none());
+    drop(producer);
+    drop(consumer);
+    assert!(buffer.producer().is_some());
+    assert!(buffer.consumer().is_some());
+}
+
+#[test]
+fn ring_buffer_passes_every_value_in_order_between_threads() {
+    const COUNT: u32 = 200_000;
+    let buffer = Arc::new(RingBuffer::with_capacity(64));
+    let producer_buffer = buffer.clone();
+    let producer = thread::spawn(move || {
+        let mut producer = producer_buffer.producer().unwrap();
+        for value in 0..COUNT {
+            while producer.push(value).is_err() {
+                thread::yield_now();
+            }
+        }
+    });
+    let mut consumer = buffer.consumer().unwrap();
+    let mut expected = 0;
+    while expected < COUNT {
+        match consumer.pop() {
+            Some(value) => {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+    assert!(buffer.is_empty());
+}
@@ -0,0 +1,47 @@
+use crate::utils::db::DB;
+use crate::utils::lerp;
+use crate::utils::param_enum::ParamEnum;
+use derive_more::Display;
+
+#[derive_aliases::derive(..Copy, Debug, Display, Default, ..Eq, ..SerDe)]
+pub enum VelocityCurve {
+    #[default]
+    Linear,
+    /// Linear in dB, spanning [`VelocityCurve::EXPONENTIAL_RANGE`]
+    Exponential,
+    /// Ignores velocity
+    Fixed,
+}
+
+impl ParamEnum for VelocityCurve {
+    const ALL: &'static [Self] = &[
+        VelocityCurve::Linear,
+        VelocityCurve::Exponential,
+        VelocityCurve::Fixed,
+    ];
+}
+
+impl VelocityCurve {
+    pub const EXPONENTIAL_RANGE: DB<f32> = DB(48.0);
+
+    /// Linear gain for a velocity in `0.0..=1.0`, always `1.0` at full velocity.
+    pub fn gain(self, velocity: f32) -> f32 {
+        match self {
+            VelocityCurve::Linear => velocity,
+            VelocityCurve::Exponential => DB((velocity - 1.0) * Self::EXPONENTIAL_RANGE.db()).linear(),
+            VelocityCurve::Fixed => 1.0,
+        }
+    }
+}
+
+/// Offset added to the oscillators' `hf_rolloff` for a velocity in `0.0..=1.0`,
+/// none at full velocity and `-amount` at zero velocity.
+pub fn hf_rolloff_offset(amount: f32, velocity: f32) -> f32 {
+    amount * (velocity - 1.0)
+}
+
+/// Factor on the release duration for a release velocity in `0.0..=1.0`,
+/// none at zero velocity and down to `curve.gain(0.0)` at full velocity and amount.
+pub fn release_scale(curve: VelocityCurve, amount: f32, velocity: f32) -> f32 {
+    lerp(1.0..=curve.gain(1.0 - velocity), amount)
+}
@@ -0,0 +1,62 @@
+/// Fixed-capacity storage for voices, preallocated so the audio thread never allocates.
+///
+/// Voices are kept densely packed, so iteration only touches live voices
+/// and removal swaps the last voice into the freed slot. Order is therefore not preserved.
+pub struct VoicePool<T> {
+    voices: Vec<T>,
+    capacity: usize,
+}
+
+impl<T> VoicePool<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            voices: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.voices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.voices.len() >= self.capacity
+    }
+
+    /// Adds a voice, handing it back if the pool is full.
+    pub fn insert(&mut self, voice: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(voice);
+        }
+        self.voices.push(voice);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        self.voices.swap_remove(index)
+    }
+
+    /// Keeps only the voices for which `f` returns `true`, visiting each voice exactly once.
+    pub fn retain_mut(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        let mut index = 0;
+        while index < self.voices.len() {
+            if f(&mut self.voices[index]) {
+                index += 1;
+            } else {
+                self.voices.swap_remove(index);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + Clone {
+        self.voices.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.voices.iter_mut()
+    }
+}